@@ -1,7 +1,7 @@
 mod router;
 mod utils;
 
-use std::net::TcpListener;
+use std::{net::TcpListener, sync::Arc};
 
 use server::thread_pool::ThreadPool;
 use utils::handle_tcp_connection;
@@ -9,12 +9,15 @@ use utils::handle_tcp_connection;
 fn main() {
     let pool = ThreadPool::new(4);
     let listener = TcpListener::bind("127.0.0.1:4221").unwrap();
+    let router = Arc::new(router::routes());
 
     for stream in listener.incoming() {
         match stream {
             Ok(mut _stream) => {
-                pool.execute(|| {
-                    let _ = handle_tcp_connection(_stream);
+                let router = Arc::clone(&router);
+
+                pool.execute(move || {
+                    let _ = handle_tcp_connection(_stream, &router);
                 });
             }
             Err(e) => {
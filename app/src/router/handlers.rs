@@ -1,214 +1,95 @@
-use crate::utils::parse_directory_from_args;
-use anyhow::{self, Context};
-use flate2::{write::GzEncoder, Compression};
 use server::http::code::HttpCode;
 use server::http::header::HttpHeader;
+use server::http::mime::MimeRegistry;
 use server::request::Request;
-use server::response::Response;
-use std::collections::HashMap;
-use std::{fs, io::Write};
-
-use super::models::RouteReturn;
-
-pub fn handle_get_root(request: &Request, _: HashMap<String, String>) -> RouteReturn {
-    Ok((
-        Response {
-            status_code: HttpCode::Ok,
-            status_text: HttpCode::Ok.to_string(),
-            http_version: request.http_version.to_string(),
-            body: None,
-            headers: None,
-        }
-        .to_string(),
-        None,
-    ))
+use server::response::{Body, Response, ServerError};
+use server::router::extract::{Header, Path, UserAgent};
+use server::router::{Handler, Params, RouteReturn};
+use server::url::path::safe_join;
+use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{Read, Seek};
+
+pub fn handle_get_root(_: &Request, _: Params) -> Response {
+    Response::ok().build()
 }
 
-pub fn handle_get_user_agent(request: &Request, _: HashMap<String, String>) -> RouteReturn {
-    let user_agent = &request.user_agent;
-    let length = user_agent.as_ref().map(|ua| ua.len()).unwrap_or(0);
-    let headers = Some(Vec::from([
-        HttpHeader::ContentType("text/plain".to_string()),
-        HttpHeader::ContentLength(length),
-    ]));
+pub fn handle_get_user_agent(user_agent: Option<Header<UserAgent>>) -> Response {
+    let user_agent = user_agent.map(|Header(UserAgent(ua))| ua);
 
-    Ok((
-        Response {
-            status_code: HttpCode::Ok,
-            status_text: HttpCode::Ok.to_string(),
-            http_version: request.http_version.to_string(),
-            headers,
-            body: user_agent.to_owned(),
-        }
-        .to_string(),
-        None,
-    ))
+    Response::ok()
+        .header(HttpHeader::ContentType("text/plain".to_string()))
+        .body(user_agent)
+        .build()
 }
 
-pub fn handle_get_echo(request: &Request, params: HashMap<String, String>) -> RouteReturn {
-    let echo_str = params
-        .get("str")
-        .context("Missing 'str' parameter in request parameters")?
-        .to_string();
-
-    match &request.accept_encoding {
-        Some(encoding) => {
-            if encoding.contains("gzip") {
-                let mut encoder = GzEncoder::new(vec![], Compression::default());
-                let _ = encoder.write_all(&echo_str.as_bytes());
-                let compressed_data = encoder.finish()?;
-
-                let headers = Some(Vec::from([
-                    HttpHeader::ContentType("text/plain".to_string()),
-                    HttpHeader::ContentEncoding("gzip".to_string()),
-                    HttpHeader::ContentLength(compressed_data.len()),
-                ]));
-
-                return Ok((
-                    Response {
-                        status_code: HttpCode::Ok,
-                        status_text: HttpCode::Ok.to_string(),
-                        http_version: request.http_version.to_string(),
-                        headers,
-                        body: None,
-                    }
-                    .to_string(),
-                    Some(compressed_data),
-                ));
-            } else {
-                let length = echo_str.len();
-                let headers = Some(Vec::from([
-                    HttpHeader::ContentType("text/plain".to_string()),
-                    HttpHeader::ContentLength(length),
-                ]));
-
-                return Ok((
-                    Response {
-                        status_code: HttpCode::Ok,
-                        status_text: HttpCode::Ok.to_string(),
-                        http_version: request.http_version.to_string(),
-                        headers,
-                        body: Some(echo_str.to_string()),
-                    }
-                    .to_string(),
-                    None,
-                ));
-            }
-        }
-        None => {
-            let length = echo_str.len();
-            let headers = Some(Vec::from([
-                HttpHeader::ContentType("text/plain".to_string()),
-                HttpHeader::ContentLength(length),
-            ]));
-
-            let res = Response {
-                status_code: HttpCode::Ok,
-                status_text: HttpCode::Ok.to_string(),
-                http_version: request.http_version.to_string(),
-                headers,
-                body: Some(echo_str.to_string()),
-            }
-            .to_string();
-
-            return Ok((
-                res,
-                None,
-            ));
-        }
-    }
+/// Echoes the path segment back. Compression is left to the `Compression` layer of the route.
+pub fn handle_get_echo(Path(echo_str): Path<String>) -> Response {
+    Response::ok()
+        .header(HttpHeader::ContentType("text/plain".to_string()))
+        .body(echo_str)
+        .build()
 }
 
-pub fn handle_get_files(request: &Request, params: HashMap<String, String>) -> RouteReturn {
-    let file_name = params
-        .get("file_name")
-        .context("Missing file_name parameter")?
-        .to_string();
-    let directory = parse_directory_from_args();
-    let file_contents = fs::read_to_string(format!("{}{}", directory, file_name));
-
-    match file_contents {
-        Ok(contents) => {
-            let length = contents.len();
-            let headers = Some(Vec::from([
-                HttpHeader::ContentType("application/octet-stream".to_string()),
-                HttpHeader::ContentLength(length),
-            ]));
+/// Serves files from `directory`, with Content-Type looked up in `mime_registry`.
+pub struct ServeFiles {
+    pub directory: PathBuf,
+    pub mime_registry: MimeRegistry,
+}
 
-            return Ok((
-                Response {
-                    status_code: HttpCode::Ok,
-                    status_text: HttpCode::Ok.to_string(),
-                    http_version: request.http_version.to_string(),
-                    headers,
-                    body: Some(contents),
-                }
-                .to_string(),
-                None,
-            ));
+impl Handler for ServeFiles {
+    fn call(&self, _: &Request, params: Params) -> RouteReturn {
+        let file_name = params
+            .get("file_name")
+            .ok_or_else(|| ServerError::bad_request("Missing file name"))?;
+        let path = match safe_join(&self.directory, file_name) {
+            Ok(path) => path,
+            Err(_) => return Err(ServerError::new(HttpCode::Forbidden, "Path not allowed").into()),
+        };
+        if !path.is_file() {
+            return Err(ServerError::not_found("File not found").into());
         }
-        Err(_) => {
-            return Ok((
-                Response {
-                    status_code: HttpCode::NotFound,
-                    status_text: HttpCode::NotFound.to_string(),
-                    http_version: request.http_version.to_string(),
-                    headers: None,
-                    body: None,
-                }
-                .to_string(),
-                None,
+
+        // Only the start of the file is needed to sniff its type; the rest is streamed.
+        let mut file = File::open(&path)?;
+        let mut head = Vec::new();
+        (&mut file).take(512).read_to_end(&mut head)?;
+        file.rewind()?;
+        let content_type = self.mime_registry.content_type(&path, &head);
+
+        // Browsers must not second-guess the type either: a sniffed `text/html` would let an
+        // uploaded file run scripts.
+        Ok(Response::ok()
+            .header(HttpHeader::ContentType(content_type))
+            .header(HttpHeader::Custom(
+                "X-Content-Type-Options".to_string(),
+                "nosniff".to_string(),
             ))
-        }
+            .body(Body::file(file)?)
+            .build())
     }
 }
 
-pub fn handle_post_files(request: &Request, params: HashMap<String, String>) -> RouteReturn {
-    let file_name = params
-        .get("file_name")
-        .context("Missing file_name parameter")?
-        .to_string();
-    let directory = parse_directory_from_args();
-
-    if let Some(cl) = request.content_length {
-        let body = &request.request[request.request.len() - 1][0..cl];
+/// Stores request bodies as files in `directory`.
+pub struct UploadFiles {
+    pub directory: PathBuf,
+}
 
-        if let Err(_) = fs::write(format!("{}{}", directory, file_name), body) {
-            return Ok((
-                Response {
-                    status_code: HttpCode::InternalServerError,
-                    status_text: HttpCode::InternalServerError.to_string(),
-                    http_version: request.http_version.to_string(),
-                    headers: None,
-                    body: None,
-                }
-                .to_string(),
-                None,
-            ));
-        } else {
-            return Ok((
-                Response {
-                    status_code: HttpCode::Created,
-                    status_text: HttpCode::Created.to_string(),
-                    http_version: request.http_version.to_string(),
-                    headers: None,
-                    body: None,
-                }
-                .to_string(),
-                None,
-            ));
+impl Handler for UploadFiles {
+    fn call(&self, request: &Request, params: Params) -> RouteReturn {
+        let file_name = params
+            .get("file_name")
+            .ok_or_else(|| ServerError::bad_request("Missing file name"))?;
+        let path = match safe_join(&self.directory, file_name) {
+            Ok(path) => path,
+            Err(_) => return Err(ServerError::new(HttpCode::Forbidden, "Path not allowed").into()),
+        };
+
+        if request.content_length.is_none() {
+            return Err(ServerError::bad_request("Missing Content-Length header").into());
         }
-    } else {
-        return Ok((
-            Response {
-                status_code: HttpCode::InternalServerError,
-                status_text: HttpCode::InternalServerError.to_string(),
-                http_version: request.http_version.to_string(),
-                headers: None,
-                body: None,
-            }
-            .to_string(),
-            None,
-        ));
+        fs::write(path, &request.body)?;
+
+        Ok(Response::created().build())
     }
 }
@@ -1,5 +1,35 @@
-pub mod models;
-pub mod handler;
 pub mod handlers;
 
-pub use handler::handle_route;
\ No newline at end of file
+use std::path::PathBuf;
+
+use server::router::{Compression, HandlerExt, Router, SlashPolicy};
+
+use crate::utils::{parse_directory_from_args, parse_mime_registry_from_args};
+
+/// Builds the application's route table.
+pub fn routes() -> Router {
+    let directory = PathBuf::from(parse_directory_from_args());
+
+    let files = Router::new()
+        .get(
+            "/*file_name",
+            handlers::ServeFiles {
+                directory: directory.clone(),
+                mime_registry: parse_mime_registry_from_args(),
+            },
+        )
+        .post("/*file_name", handlers::UploadFiles { directory });
+
+    Router::new()
+        .trailing_slash(SlashPolicy::Redirect)
+        .duplicate_slashes(SlashPolicy::Redirect)
+        .layer(Compression::new())
+        .get("/", handlers::handle_get_root)
+        .get("/user-agent", handlers::handle_get_user_agent)
+        // Echoed strings are short, but clients still expect them compressed when they ask.
+        .get(
+            "/echo/:str",
+            handlers::handle_get_echo.with(Compression::new().threshold(0)),
+        )
+        .nest("/files", files)
+}
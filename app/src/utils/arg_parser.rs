@@ -1,5 +1,7 @@
 use std::env;
 
+use server::http::mime::MimeRegistry;
+
 pub fn parse_directory_from_args() -> String {
     let args: Vec<String> = env::args().collect();
 
@@ -9,3 +11,23 @@ pub fn parse_directory_from_args() -> String {
         .map(|window| window[1].to_string())
         .unwrap_or_else(|| "./".to_string())
 }
+
+/// Builds the MIME registry from `--mime-type <ext>=<type>` overrides and the `--sniff-mime-types` flag.
+///
+/// `--sniff-mime-types` guesses the type of files without a known extension from their contents.
+/// Only enable it for trusted files: anyone who can upload a file can choose what it is sniffed
+/// as. Markup is never sniffed as HTML or SVG, and files are sent with
+/// `X-Content-Type-Options: nosniff`, but a sniffed type is still a guess.
+pub fn parse_mime_registry_from_args() -> MimeRegistry {
+    let args: Vec<String> = env::args().collect();
+
+    let registry = args
+        .windows(2)
+        .filter(|window| window[0] == "--mime-type")
+        .filter_map(|window| window[1].split_once('='))
+        .fold(MimeRegistry::new(), |registry, (ext, mime)| {
+            registry.with(ext, mime)
+        });
+
+    registry.with_sniffing(args.iter().any(|arg| arg == "--sniff-mime-types"))
+}
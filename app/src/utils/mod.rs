@@ -1,5 +1,5 @@
 pub mod arg_parser;
 pub mod tcp_connection_handler;
 
-pub use arg_parser::parse_directory_from_args;
+pub use arg_parser::{parse_directory_from_args, parse_mime_registry_from_args};
 pub use tcp_connection_handler::handle_tcp_connection;
\ No newline at end of file
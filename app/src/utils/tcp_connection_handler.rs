@@ -1,33 +1,48 @@
-use std::{io::Write, net::TcpStream};
+use std::net::TcpStream;
 
 use anyhow::{Context, Result};
-use server::{http::code::HttpCode, request::Request, response::Response};
-
-use crate::router;
-
-pub fn handle_tcp_connection(mut stream: TcpStream) -> Result<()> {
-    let request = Request::new(&mut stream).context("Failed to parse the request")?;
-    let (response_line, data) = router::handle_route(&request).unwrap_or((
-        Response {
-            status_code: HttpCode::InternalServerError,
-            status_text: HttpCode::InternalServerError.to_string(),
-            http_version: request.http_version.to_string(),
-            body: None,
-            headers: None,
-        }
-        .to_string(),
-        None,
-    ));
+use server::{
+    http::code::HttpCode,
+    request::{
+        builder::{BodyError, HeadTooLargeError, UnsupportedVersionError},
+        Request,
+    },
+    response::IntoResponse,
+    router::Router,
+    url::percent::PercentDecodeError,
+};
 
+pub fn handle_tcp_connection(mut stream: TcpStream, router: &Router) -> Result<()> {
+    // Responses are written in as few writes as possible, so there is nothing to gain from
+    // Nagle's algorithm delaying the last packet of each one.
     stream
-        .write_all(response_line.as_bytes())
-        .context("Failed to write response line to stream")?;
+        .set_nodelay(true)
+        .context("Failed to disable Nagle's algorithm")?;
 
-    if let Some(d) = data {
-        stream
-            .write_all(&d)
-            .context("Failed to write response line to stream")?;
-    }
+    let mut request = match Request::new(&mut stream) {
+        Ok(request) => request,
+        Err(e) => {
+            let code = if e.downcast_ref::<PercentDecodeError>().is_some() {
+                HttpCode::BadRequest
+            } else if e.downcast_ref::<UnsupportedVersionError>().is_some() {
+                HttpCode::HttpVersionNotSupported
+            } else if e.downcast_ref::<HeadTooLargeError>().is_some() {
+                HttpCode::RequestHeaderFieldsTooLarge
+            } else if let Some(error) = e.downcast_ref::<BodyError>() {
+                error.code()
+            } else {
+                return Err(e.context("Failed to parse the request"));
+            };
+
+            return code
+                .into_response()
+                .write_to(&mut stream)
+                .context("Failed to write response to stream");
+        }
+    };
 
-    Ok(())
+    router
+        .handle(&mut request)
+        .write_to(&mut stream)
+        .context("Failed to write response to stream")
 }
@@ -0,0 +1,159 @@
+//! # Accept Module
+//!
+//! This module implements content negotiation with the `Accept` request header, as defined in
+//! RFC 9110, Section 12.5.1.
+//!
+//! The header lists media ranges (`text/html`, `text/*` or `*/*`), each with an optional weight
+//! `q` between 0 and 1. Every media type the server can produce gets the weight of the most
+//! specific range matching it, and the type with the highest weight wins. A weight of 0 means
+//! "not acceptable".
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::http::accept::negotiate;
+//!
+//! let available = ["text/plain", "text/html", "application/json"];
+//!
+//! let browser = "text/html,application/xhtml+xml,*/*;q=0.8";
+//! assert_eq!(negotiate(Some(browser), &available), Some("text/html"));
+//! assert_eq!(negotiate(Some("application/*"), &available), Some("application/json"));
+//! assert_eq!(negotiate(None, &available), Some("text/plain"));
+//! assert_eq!(negotiate(Some("image/png"), &available), None);
+//! ```
+
+/// One media range of an `Accept` header.
+#[derive(Debug, PartialEq, Clone)]
+struct MediaRange<'a> {
+    kind: &'a str,
+    subtype: &'a str,
+    q: f32,
+}
+
+impl<'a> MediaRange<'a> {
+    /// Parses a media range like `text/html;q=0.8`, ignoring other parameters.
+    fn parse(range: &'a str) -> Option<MediaRange<'a>> {
+        let mut parts = range.split(';');
+        let (kind, subtype) = parts.next()?.trim().split_once('/')?;
+
+        let q = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+            .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+
+        Some(MediaRange {
+            kind: kind.trim(),
+            subtype: subtype.trim(),
+            q,
+        })
+    }
+
+    /// Returns how specifically the range matches `kind/subtype`, or `None` if it does not.
+    fn specificity(&self, kind: &str, subtype: &str) -> Option<u8> {
+        match (self.kind, self.subtype) {
+            ("*", "*") => Some(0),
+            (k, "*") if k.eq_ignore_ascii_case(kind) => Some(1),
+            (k, s) if k.eq_ignore_ascii_case(kind) && s.eq_ignore_ascii_case(subtype) => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the weight the `accept` header gives to `media_type`, from 0 to 1.
+///
+/// A missing header accepts everything with a weight of 1.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::http::accept::quality;
+///
+/// assert_eq!(quality(Some("text/*;q=0.5, text/html"), "text/html"), 1.0);
+/// assert_eq!(quality(Some("text/*;q=0.5, text/html"), "text/plain"), 0.5);
+/// assert_eq!(quality(Some("text/*;q=0.5, text/html"), "image/png"), 0.0);
+/// ```
+pub fn quality(accept: Option<&str>, media_type: &str) -> f32 {
+    let Some(accept) = accept else {
+        return 1.0;
+    };
+    let Some((kind, subtype)) = media_type.split(';').next().and_then(|m| m.trim().split_once('/'))
+    else {
+        return 0.0;
+    };
+
+    accept
+        .split(',')
+        .filter_map(MediaRange::parse)
+        .filter_map(|range| Some((range.specificity(kind, subtype)?, range.q)))
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or(0.0, |(_, q)| q)
+}
+
+/// Picks the media type from `available` that the `accept` header prefers.
+///
+/// Ties are broken by the order of `available`, so the server's preferred type should come first.
+///
+/// # Returns
+///
+/// - `Some(media_type)`: The preferred acceptable type. Without an `Accept` header, this is the
+///   first available type.
+/// - `None`: If none of the available types are acceptable.
+pub fn negotiate<'a>(accept: Option<&str>, available: &[&'a str]) -> Option<&'a str> {
+    available
+        .iter()
+        .map(|media_type| (*media_type, quality(accept, media_type)))
+        .filter(|(_, q)| *q > 0.0)
+        .fold(None, |best: Option<(&str, f32)>, (media_type, q)| match best {
+            Some((_, best_q)) if best_q >= q => best,
+            _ => Some((media_type, q)),
+        })
+        .map(|(media_type, _)| media_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_media_range() {
+        assert_eq!(
+            MediaRange::parse(" text/html ; level=1; q=0.7 "),
+            Some(MediaRange {
+                kind: "text",
+                subtype: "html",
+                q: 0.7
+            })
+        );
+        assert_eq!(MediaRange::parse("*/*").map(|r| r.q), Some(1.0));
+        assert_eq!(MediaRange::parse("text/html;q=7").map(|r| r.q), Some(1.0));
+        assert_eq!(MediaRange::parse("text/html;q=abc").map(|r| r.q), Some(1.0));
+        assert_eq!(MediaRange::parse("garbage"), None);
+    }
+
+    #[test]
+    fn test_most_specific_range_wins() {
+        let accept = Some("*/*;q=0.1, text/*;q=0.5, text/html;q=0.9, text/csv;q=0");
+
+        assert_eq!(quality(accept, "text/html; charset=utf-8"), 0.9);
+        assert_eq!(quality(accept, "text/plain"), 0.5);
+        assert_eq!(quality(accept, "TEXT/CSV"), 0.0);
+        assert_eq!(quality(accept, "image/png"), 0.1);
+        assert_eq!(quality(None, "image/png"), 1.0);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let available = ["text/plain", "text/html", "application/problem+json"];
+
+        assert_eq!(negotiate(Some("*/*"), &available), Some("text/plain"));
+        assert_eq!(
+            negotiate(Some("application/problem+json, */*;q=0.5"), &available),
+            Some("application/problem+json")
+        );
+        assert_eq!(negotiate(Some("text/*, text/plain;q=0.2"), &available), Some("text/html"));
+        assert_eq!(negotiate(Some("text/*;q=0"), &available), None);
+        assert_eq!(negotiate(Some(""), &available), None);
+    }
+}
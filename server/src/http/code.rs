@@ -35,8 +35,14 @@ pub enum HttpCode {
     MovedPermanently = 301,
     /// 302 Found: The requested resource resides temporarily under a different URI.
     Found = 302,
+    /// 303 See Other: The response to the request can be found under a different URI, with a GET request.
+    SeeOther = 303,
     /// 304 Not Modified: Indicates that the resource has not been modified since the last request.
     NotModified = 304,
+    /// 307 Temporary Redirect: The requested resource resides temporarily under a different URI, and the method must not change.
+    TemporaryRedirect = 307,
+    /// 308 Permanent Redirect: The requested resource has been assigned a new permanent URI, and the method must not change.
+    PermanentRedirect = 308,
     /// 400 Bad Request: The server could not understand the request due to invalid syntax.
     BadRequest = 400,
     /// 401 Unauthorized: The client must authenticate itself to get the requested response.
@@ -47,6 +53,10 @@ pub enum HttpCode {
     NotFound = 404,
     /// 405 Method Not Allowed: The request method is known by the server but has been disabled and cannot be used.
     MethodNotAllowed = 405,
+    /// 413 Content Too Large: The request body is larger than the server is willing to process.
+    ContentTooLarge = 413,
+    /// 431 Request Header Fields Too Large: The request line and headers are larger than the server is willing to process.
+    RequestHeaderFieldsTooLarge = 431,
     /// 500 Internal Server Error: The server has encountered a situation it doesn't know how to handle.
     InternalServerError = 500,
     /// 501 Not Implemented: The request method is not supported by the server and cannot be handled.
@@ -55,6 +65,8 @@ pub enum HttpCode {
     BadGateway = 502,
     /// 503 Service Unavailable: The server is not ready to handle the request.
     ServiceUnavailable = 503,
+    /// 505 HTTP Version Not Supported: The HTTP version used in the request is not supported by the server.
+    HttpVersionNotSupported = 505,
 }
 
 impl HttpCode {
@@ -76,16 +88,22 @@ impl HttpCode {
             HttpCode::NoContent => 204,
             HttpCode::MovedPermanently => 301,
             HttpCode::Found => 302,
+            HttpCode::SeeOther => 303,
             HttpCode::NotModified => 304,
+            HttpCode::TemporaryRedirect => 307,
+            HttpCode::PermanentRedirect => 308,
             HttpCode::BadRequest => 400,
             HttpCode::Unauthorized => 401,
             HttpCode::Forbidden => 403,
             HttpCode::NotFound => 404,
             HttpCode::MethodNotAllowed => 405,
+            HttpCode::ContentTooLarge => 413,
+            HttpCode::RequestHeaderFieldsTooLarge => 431,
             HttpCode::InternalServerError => 500,
             HttpCode::NotImplemented => 501,
             HttpCode::BadGateway => 502,
             HttpCode::ServiceUnavailable => 503,
+            HttpCode::HttpVersionNotSupported => 505,
         }
     }
 }
@@ -109,16 +127,22 @@ impl Display for HttpCode {
             HttpCode::NoContent => "No Content",
             HttpCode::MovedPermanently => "Moved Permanently",
             HttpCode::Found => "Found",
+            HttpCode::SeeOther => "See Other",
             HttpCode::NotModified => "Not Modified",
+            HttpCode::TemporaryRedirect => "Temporary Redirect",
+            HttpCode::PermanentRedirect => "Permanent Redirect",
             HttpCode::BadRequest => "Bad Request",
             HttpCode::Unauthorized => "Unauthorized",
             HttpCode::Forbidden => "Forbidden",
             HttpCode::NotFound => "Not Found",
             HttpCode::MethodNotAllowed => "Method Not Allowed",
+            HttpCode::ContentTooLarge => "Content Too Large",
+            HttpCode::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
             HttpCode::InternalServerError => "Internal Server Error",
             HttpCode::NotImplemented => "Not Implemented",
             HttpCode::BadGateway => "Bad Gateway",
             HttpCode::ServiceUnavailable => "Service Unavailable",
+            HttpCode::HttpVersionNotSupported => "HTTP Version Not Supported",
         };
         write!(f, "{}", result)
     }
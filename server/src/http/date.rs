@@ -0,0 +1,522 @@
+//! # HTTP Date Module
+//!
+//! This module defines the `HttpDate` struct, representing a timestamp as used by HTTP header fields
+//! such as `Date`, `Last-Modified`, `Expires` and `Retry-After` (RFC 9110, Section 5.6.7).
+//! It provides functionality to format dates in the preferred IMF-fixdate form using the `Display` trait,
+//! and to parse all three formats recipients are required to accept using the `FromStr` trait:
+//!
+//! - **IMF-fixdate:** `Sun, 06 Nov 1994 08:49:37 GMT`
+//! - **RFC 850:** `Sunday, 06-Nov-94 08:49:37 GMT`
+//! - **asctime:** `Sun Nov  6 08:49:37 1994`
+//!
+//! Parsing is strict: the day name must be the one the date falls on, and leap seconds (`:60`) are
+//! rejected, since `SystemTime` cannot express them. Invalid dates are reported as errors, which
+//! RFC 9110 lets recipients treat like a missing header.
+//!
+//! Dates convert to and from `std::time::SystemTime`. HTTP dates have a resolution of one second,
+//! so sub-second precision is truncated when converting from a `SystemTime`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::http::date::HttpDate;
+//! use std::str::FromStr;
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//!
+//! let date = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+//! assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+//!
+//! let parsed = HttpDate::from_str("Sunday, 06-Nov-94 08:49:37 GMT").unwrap();
+//! assert_eq!(parsed, date);
+//! assert_eq!(SystemTime::from(parsed), UNIX_EPOCH + Duration::from_secs(784111777));
+//! ```
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SHORT_DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const LONG_DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Seconds from the Unix epoch to the last second of year 9999, `Fri, 31 Dec 9999 23:59:59 GMT`.
+const MAX_SECS: u64 = 253_402_300_799;
+
+/// Represents a point in time with the one-second resolution used by HTTP.
+///
+/// Only dates from the Unix epoch up to the end of year 9999 can be represented, which covers
+/// every date that can be written in the IMF-fixdate format.
+///
+/// ## Examples
+///
+/// ```rust
+/// use server::http::date::HttpDate;
+/// use std::time::UNIX_EPOCH;
+///
+/// let date = HttpDate::from(UNIX_EPOCH);
+/// assert_eq!(date.to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct HttpDate {
+    /// Seconds elapsed since the Unix epoch.
+    secs: u64,
+}
+
+/// The broken-down calendar fields of an `HttpDate`, all in UTC.
+struct DateTime {
+    year: u64,
+    month: u64,
+    day: u64,
+    hour: u64,
+    minute: u64,
+    second: u64,
+    /// Index into `SHORT_DAY_NAMES` of the day name.
+    weekday: usize,
+}
+
+impl HttpDate {
+    /// Returns the current time as an `HttpDate`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::http::date::HttpDate;
+    ///
+    /// let now = HttpDate::now();
+    /// assert!(now.to_string().ends_with(" GMT"));
+    /// ```
+    pub fn now() -> HttpDate {
+        HttpDate::from(SystemTime::now())
+    }
+
+    /// Builds an `HttpDate` from validated calendar fields.
+    fn from_date_time(dt: &DateTime) -> Result<HttpDate, &'static str> {
+        if !(1970..=9999).contains(&dt.year)
+            || !(1..=12).contains(&dt.month)
+            || dt.day < 1
+            || dt.day > days_in_month(dt.year, dt.month)
+            || dt.hour > 23
+            || dt.minute > 59
+            || dt.second > 59
+        {
+            return Err("Invalid HTTP date");
+        }
+
+        let days = days_from_civil(dt.year, dt.month, dt.day);
+        let secs = days * SECONDS_PER_DAY + dt.hour * 3600 + dt.minute * 60 + dt.second;
+        let date = HttpDate { secs };
+
+        if date.weekday() != dt.weekday {
+            return Err("Invalid HTTP date");
+        }
+
+        Ok(date)
+    }
+
+    /// Splits the timestamp into its calendar fields.
+    fn to_date_time(self) -> DateTime {
+        let days = self.secs / SECONDS_PER_DAY;
+        let time = self.secs % SECONDS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time % 3600 / 60,
+            second: time % 60,
+            weekday: self.weekday(),
+        }
+    }
+
+    /// Index into `SHORT_DAY_NAMES` of the week day this date falls on.
+    fn weekday(self) -> usize {
+        // 1970-01-01 was a Thursday.
+        ((self.secs / SECONDS_PER_DAY + 3) % 7) as usize
+    }
+
+    /// Parses an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+    fn parse_imf_fixdate(s: &str) -> Option<DateTime> {
+        let (day_name, rest) = s.split_once(", ")?;
+        let weekday = SHORT_DAY_NAMES.iter().position(|&d| d == day_name)?;
+
+        let parts = rest.split(' ').collect::<Vec<&str>>();
+        if parts.len() != 5 || parts[4] != "GMT" || parts[0].len() != 2 || parts[2].len() != 4 {
+            return None;
+        }
+
+        let (hour, minute, second) = parse_time(parts[3])?;
+
+        Some(DateTime {
+            year: parse_digits(parts[2])?,
+            month: parse_month(parts[1])?,
+            day: parse_digits(parts[0])?,
+            hour,
+            minute,
+            second,
+            weekday,
+        })
+    }
+
+    /// Parses an obsolete RFC 850 date, e.g. `Sunday, 06-Nov-94 08:49:37 GMT`.
+    fn parse_rfc850(s: &str) -> Option<DateTime> {
+        let (day_name, rest) = s.split_once(", ")?;
+        let weekday = LONG_DAY_NAMES.iter().position(|&d| d == day_name)?;
+
+        let parts = rest.split(' ').collect::<Vec<&str>>();
+        if parts.len() != 3 || parts[2] != "GMT" {
+            return None;
+        }
+
+        let date = parts[0].split('-').collect::<Vec<&str>>();
+        if date.len() != 3 || date[0].len() != 2 || date[2].len() != 2 {
+            return None;
+        }
+
+        // Two-digit years are interpreted within the range 1970-2069.
+        let short_year = parse_digits(date[2])?;
+        let year = if short_year < 70 {
+            2000 + short_year
+        } else {
+            1900 + short_year
+        };
+        let (hour, minute, second) = parse_time(parts[1])?;
+
+        Some(DateTime {
+            year,
+            month: parse_month(date[1])?,
+            day: parse_digits(date[0])?,
+            hour,
+            minute,
+            second,
+            weekday,
+        })
+    }
+
+    /// Parses an obsolete asctime date, e.g. `Sun Nov  6 08:49:37 1994`.
+    fn parse_asctime(s: &str) -> Option<DateTime> {
+        let (day_name, rest) = s.split_once(' ')?;
+        let weekday = SHORT_DAY_NAMES.iter().position(|&d| d == day_name)?;
+
+        let (month, rest) = rest.split_once(' ')?;
+        // The day of month is padded with a space rather than a zero.
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+
+        let parts = rest.split(' ').collect::<Vec<&str>>();
+        if parts.len() != 3 || parts[0].is_empty() || parts[0].len() > 2 || parts[2].len() != 4 {
+            return None;
+        }
+
+        let (hour, minute, second) = parse_time(parts[1])?;
+
+        Some(DateTime {
+            year: parse_digits(parts[2])?,
+            month: parse_month(month)?,
+            day: parse_digits(parts[0])?,
+            hour,
+            minute,
+            second,
+            weekday,
+        })
+    }
+}
+
+impl From<SystemTime> for HttpDate {
+    /// Converts a `SystemTime` into an `HttpDate`, truncating sub-second precision.
+    ///
+    /// Times before the Unix epoch are clamped to the epoch, and times after the end of year 9999
+    /// to its last second.
+    fn from(time: SystemTime) -> HttpDate {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs().min(MAX_SECS))
+            .unwrap_or(0);
+
+        HttpDate { secs }
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    /// Converts an `HttpDate` back into a `SystemTime`.
+    fn from(date: HttpDate) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(date.secs)
+    }
+}
+
+impl Display for HttpDate {
+    /// Formats the `HttpDate` as an IMF-fixdate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::http::date::HttpDate;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let date = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+    /// assert_eq!(format!("{}", date), "Sun, 06 Nov 1994 08:49:37 GMT");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dt = self.to_date_time();
+
+        write!(
+            f,
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            SHORT_DAY_NAMES[self.weekday()],
+            dt.day,
+            MONTH_NAMES[(dt.month - 1) as usize],
+            dt.year,
+            dt.hour,
+            dt.minute,
+            dt.second
+        )
+    }
+}
+
+impl FromStr for HttpDate {
+    type Err = &'static str;
+
+    /// Parses a string into an `HttpDate`.
+    ///
+    /// Accepts IMF-fixdate as well as the obsolete RFC 850 and asctime formats.
+    /// Surrounding whitespace is ignored; anything else that does not match one of
+    /// the formats exactly results in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::http::date::HttpDate;
+    /// use std::str::FromStr;
+    ///
+    /// let imf = HttpDate::from_str("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+    /// let asctime = HttpDate::from_str("Sun Nov  6 08:49:37 1994").unwrap();
+    /// assert_eq!(imf, asctime);
+    ///
+    /// let invalid = HttpDate::from_str("yesterday");
+    /// assert_eq!(invalid.unwrap_err(), "Invalid HTTP date");
+    /// ```
+    fn from_str(s: &str) -> Result<HttpDate, Self::Err> {
+        let s = s.trim();
+
+        let date_time = Self::parse_imf_fixdate(s)
+            .or_else(|| Self::parse_rfc850(s))
+            .or_else(|| Self::parse_asctime(s))
+            .ok_or("Invalid HTTP date")?;
+
+        Self::from_date_time(&date_time)
+    }
+}
+
+/// Parses a string made only of ASCII digits.
+fn parse_digits(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    s.parse().ok()
+}
+
+/// Parses a three-letter month name into its number (1-12).
+fn parse_month(s: &str) -> Option<u64> {
+    MONTH_NAMES
+        .iter()
+        .position(|&m| m == s)
+        .map(|i| i as u64 + 1)
+}
+
+/// Parses a `HH:MM:SS` time of day.
+fn parse_time(s: &str) -> Option<(u64, u64, u64)> {
+    let parts = s.split(':').collect::<Vec<&str>>();
+    if parts.len() != 3 || parts.iter().any(|p| p.len() != 2) {
+        return None;
+    }
+
+    Some((
+        parse_digits(parts[0])?,
+        parse_digits(parts[1])?,
+        parse_digits(parts[2])?,
+    ))
+}
+
+fn is_leap_year(year: u64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 for the given proleptic Gregorian date.
+///
+/// Based on Howard Hinnant's `days_from_civil` algorithm, restricted to dates after the epoch.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian `(year, month, day)` for the given number of days since 1970-01-01.
+///
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(secs: u64) -> HttpDate {
+        HttpDate::from(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn test_display_imf_fixdate() {
+        assert_eq!(date(0).to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(date(784111777).to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(date(951782400).to_string(), "Tue, 29 Feb 2000 00:00:00 GMT");
+        assert_eq!(date(253402300799).to_string(), "Fri, 31 Dec 9999 23:59:59 GMT");
+    }
+
+    #[test]
+    fn test_from_str_all_formats() {
+        let expected = date(784111777);
+
+        assert_eq!(HttpDate::from_str("Sun, 06 Nov 1994 08:49:37 GMT"), Ok(expected));
+        assert_eq!(HttpDate::from_str("Sunday, 06-Nov-94 08:49:37 GMT"), Ok(expected));
+        assert_eq!(HttpDate::from_str("Sun Nov  6 08:49:37 1994"), Ok(expected));
+        assert_eq!(HttpDate::from_str("  Sun, 06 Nov 1994 08:49:37 GMT "), Ok(expected));
+    }
+
+    #[test]
+    fn test_from_str_rfc850_two_digit_years() {
+        assert_eq!(
+            HttpDate::from_str("Thursday, 01-Jan-70 00:00:00 GMT"),
+            Ok(date(0))
+        );
+        assert_eq!(
+            HttpDate::from_str("Tuesday, 29-Feb-00 00:00:00 GMT"),
+            Ok(date(951782400))
+        );
+    }
+
+    #[test]
+    fn test_from_str_asctime_two_digit_day() {
+        assert_eq!(
+            HttpDate::from_str("Tue Feb 29 00:00:00 2000"),
+            Ok(date(951782400))
+        );
+    }
+
+    #[test]
+    fn test_from_str_checks_the_day_name() {
+        assert_eq!(
+            HttpDate::from_str("Mon, 01 Jan 2024 00:00:00 GMT").unwrap().to_string(),
+            "Mon, 01 Jan 2024 00:00:00 GMT"
+        );
+        assert_eq!(
+            HttpDate::from_str("Fri, 31 Dec 9999 23:59:59 GMT"),
+            Ok(date(MAX_SECS))
+        );
+    }
+
+    #[test]
+    fn test_from_str_invalid_dates() {
+        let invalid = [
+            "",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 94 08:49:37 GMT",
+            "Sun, 31 Nov 1994 08:49:37 GMT",
+            "Sun, 29 Feb 1900 00:00:00 GMT",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1994 8:49:37 GMT",
+            "Sun, 01 Jan 1969 00:00:00 GMT",
+            "Sunday, 06-Nov-1994 08:49:37 GMT",
+            "Sun Nov 6 08:49:37 94",
+            "Funday, 06-Nov-94 08:49:37 GMT",
+            // 2024-01-01 was a Monday.
+            "Fri, 01 Jan 2024 00:00:00 GMT",
+            "Friday, 01-Jan-24 00:00:00 GMT",
+            "Fri Jan  1 00:00:00 2024",
+            // Leap seconds are rejected rather than folded into the next second.
+            "Sat, 31 Dec 2016 23:59:60 GMT",
+            "Fri, 31 Dec 9999 23:59:60 GMT",
+        ];
+
+        for s in invalid {
+            assert_eq!(HttpDate::from_str(s), Err("Invalid HTTP date"), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn test_system_time_round_trip() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_999);
+        let date = HttpDate::from(time);
+
+        assert_eq!(SystemTime::from(date), UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert_eq!(HttpDate::from_str(&date.to_string()), Ok(date));
+    }
+
+    #[test]
+    fn test_from_system_time_before_epoch() {
+        let time = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(HttpDate::from(time), date(0));
+    }
+
+    #[test]
+    fn test_from_system_time_after_year_9999() {
+        let last = date(MAX_SECS);
+        assert_eq!(date(MAX_SECS + 1), last);
+        assert_eq!(date(u32::MAX as u64 * 1000), last);
+        assert_eq!(last.to_string(), "Fri, 31 Dec 9999 23:59:59 GMT");
+    }
+
+    #[test]
+    fn test_civil_days_round_trip() {
+        for days in (0..3_000_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+}
@@ -29,6 +29,12 @@ use std::fmt::Display;
 /// - `ContentType(String)`: Specifies the media type of the resource.
 /// - `ContentLength(usize)`: Indicates the size of the response body in bytes.
 /// - `ContentEncoding(String)`: Defines the encoding transformations that have been applied to the resource.
+/// - `Vary(String)`: Lists the request headers that the response depends on.
+/// - `Location(String)`: Indicates the URL to redirect to.
+/// - `TransferEncoding(String)`: Lists the transfer codings applied to the message body.
+/// - `Trailer(String)`: Lists the fields sent as trailers after a chunked body.
+/// - `Connection(String)`: Sets options for the current connection, such as `close`.
+/// - `Custom(String, String)`: Any other header field, as a name and a value.
 ///
 /// ## Examples
 ///
@@ -63,6 +69,42 @@ pub enum HttpHeader {
     /// Defines the encoding transformations that have been applied to the resource.
     /// For example, `gzip` or `deflate`.
     ContentEncoding(String),
+
+    /// `Vary` header field.
+    ///
+    /// Lists the request headers that were used to select the representation, so caches
+    /// do not serve it for requests that would have been answered differently.
+    /// For example, `Accept` or `Accept-Encoding`.
+    Vary(String),
+
+    /// `Location` header field.
+    ///
+    /// Indicates the URL a redirect points to, or the URL of a newly created resource.
+    /// For example, `/login` or `https://example.com/`.
+    Location(String),
+
+    /// `Transfer-Encoding` header field.
+    ///
+    /// Lists the transfer codings applied to the message body, in the order they were applied.
+    /// For example, `chunked`.
+    TransferEncoding(String),
+
+    /// `Trailer` header field.
+    ///
+    /// Lists the names of the fields that will be sent as trailers after a chunked body.
+    /// For example, `Digest`.
+    Trailer(String),
+
+    /// `Connection` header field.
+    ///
+    /// Sets options for the current connection.
+    /// For example, `close` or `keep-alive`.
+    Connection(String),
+
+    /// A header field without a dedicated variant, as a name and a value.
+    ///
+    /// For example, `("Server-Timing", "db;dur=53")`.
+    Custom(String, String),
 }
 
 impl Display for HttpHeader {
@@ -84,6 +126,12 @@ impl Display for HttpHeader {
             HttpHeader::ContentType(value) => write!(f, "Content-Type: {}", value),
             HttpHeader::ContentLength(value) => write!(f, "Content-Length: {}", value),
             HttpHeader::ContentEncoding(value) => write!(f, "Content-Encoding: {}", value),
+            HttpHeader::Vary(value) => write!(f, "Vary: {}", value),
+            HttpHeader::Location(value) => write!(f, "Location: {}", value),
+            HttpHeader::TransferEncoding(value) => write!(f, "Transfer-Encoding: {}", value),
+            HttpHeader::Trailer(value) => write!(f, "Trailer: {}", value),
+            HttpHeader::Connection(value) => write!(f, "Connection: {}", value),
+            HttpHeader::Custom(name, value) => write!(f, "{}: {}", name, value),
         }
     }
 }
@@ -122,15 +170,45 @@ mod tests {
         assert_eq!(format!("{}", header_empty), "Content-Encoding: ");
     }
 
+    #[test]
+    fn test_display_vary() {
+        let header = HttpHeader::Vary("Accept".to_string());
+        assert_eq!(format!("{}", header), "Vary: Accept");
+    }
+
+    #[test]
+    fn test_display_location() {
+        let header = HttpHeader::Location("/login".to_string());
+        assert_eq!(format!("{}", header), "Location: /login");
+    }
+
+    #[test]
+    fn test_display_transfer_encoding() {
+        let header = HttpHeader::TransferEncoding("chunked".to_string());
+        assert_eq!(format!("{}", header), "Transfer-Encoding: chunked");
+    }
+
+    #[test]
+    fn test_display_trailer_connection_and_custom() {
+        let header = HttpHeader::Trailer("Digest".to_string());
+        assert_eq!(format!("{}", header), "Trailer: Digest");
+
+        let header = HttpHeader::Connection("close".to_string());
+        assert_eq!(format!("{}", header), "Connection: close");
+
+        let header = HttpHeader::Custom("X-Request-Id".to_string(), "42".to_string());
+        assert_eq!(format!("{}", header), "X-Request-Id: 42");
+    }
+
     #[test]
     fn test_multiple_headers() {
-        let headers = vec![
+        let headers = [
             HttpHeader::ContentType("text/html".to_string()),
             HttpHeader::ContentLength(1024),
             HttpHeader::ContentEncoding("deflate".to_string()),
         ];
 
-        let expected = vec![
+        let expected = [
             "Content-Type: text/html",
             "Content-Length: 1024",
             "Content-Encoding: deflate",
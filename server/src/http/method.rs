@@ -24,7 +24,7 @@ use std::str::{self, FromStr};
 ///
 /// The `HttpMethod` enum includes common HTTP methods used in web communication.
 /// Each variant corresponds to a specific HTTP request method.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum HttpMethod {
     /// `GET` method.
     ///
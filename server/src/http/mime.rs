@@ -0,0 +1,343 @@
+//! # MIME Module
+//!
+//! This module defines the `MimeRegistry` struct, which maps file extensions to the media types
+//! sent in the `Content-Type` header when serving files.
+//! Text-based types are registered with a `charset=utf-8` parameter so browsers decode them correctly.
+//!
+//! The registry comes pre-populated with common web types. Entries can be added or overridden,
+//! and content sniffing can be enabled for files that have no (known) extension.
+//!
+//! Sniffing never yields a type browsers render as a document, such as `text/html`,
+//! `application/xml` or `image/svg+xml`: a file uploaded without an extension could otherwise run
+//! scripts in the origin of the server. Markup is served as plain text instead. Servers should
+//! still send `X-Content-Type-Options: nosniff`, so that browsers do not sniff either.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::http::mime::MimeRegistry;
+//! use std::path::Path;
+//!
+//! let registry = MimeRegistry::new()
+//!     .with("md", "text/markdown; charset=utf-8")
+//!     .with_sniffing(true);
+//!
+//! assert_eq!(registry.lookup(Path::new("index.html")), Some("text/html; charset=utf-8"));
+//! assert_eq!(registry.lookup(Path::new("README.md")), Some("text/markdown; charset=utf-8"));
+//! assert_eq!(registry.content_type(Path::new("logo"), b"\x89PNG\r\n\x1a\n..."), "image/png");
+//! ```
+
+use std::{collections::HashMap, path::Path};
+
+/// Media type used when nothing more specific is known about a file.
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// Extensions registered by `MimeRegistry::new`.
+const DEFAULT_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html; charset=utf-8"),
+    ("htm", "text/html; charset=utf-8"),
+    ("css", "text/css; charset=utf-8"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("mjs", "text/javascript; charset=utf-8"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("xml", "application/xml; charset=utf-8"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("webmanifest", "application/manifest+json"),
+    ("wasm", "application/wasm"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+/// Magic byte prefixes recognised by `MimeRegistry::sniff`.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\0asm", "application/wasm"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+];
+
+/// Maps file extensions to media types.
+///
+/// Extensions are matched case-insensitively and without the leading dot.
+///
+/// ## Examples
+///
+/// ```rust
+/// use server::http::mime::MimeRegistry;
+/// use std::path::Path;
+///
+/// let registry = MimeRegistry::new();
+/// assert_eq!(registry.lookup(Path::new("styles/site.CSS")), Some("text/css; charset=utf-8"));
+/// assert_eq!(registry.lookup(Path::new("archive.unknown")), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MimeRegistry {
+    types: HashMap<String, String>,
+    sniffing: bool,
+}
+
+impl MimeRegistry {
+    /// Creates a registry pre-populated with common web media types.
+    ///
+    /// Content sniffing is disabled.
+    pub fn new() -> MimeRegistry {
+        let types = DEFAULT_TYPES
+            .iter()
+            .map(|(ext, mime)| (ext.to_string(), mime.to_string()))
+            .collect();
+
+        MimeRegistry {
+            types,
+            sniffing: false,
+        }
+    }
+
+    /// Creates a registry without any registered extensions.
+    pub fn empty() -> MimeRegistry {
+        MimeRegistry {
+            types: HashMap::new(),
+            sniffing: false,
+        }
+    }
+
+    /// Registers `mime` for files with the extension `ext`, replacing any previous entry.
+    ///
+    /// A leading dot in `ext` is ignored.
+    pub fn insert(&mut self, ext: &str, mime: &str) {
+        self.types.insert(Self::normalize_extension(ext), mime.to_string());
+    }
+
+    /// Builder-style variant of `insert`.
+    pub fn with(mut self, ext: &str, mime: &str) -> MimeRegistry {
+        self.insert(ext, mime);
+        self
+    }
+
+    /// Enables or disables content sniffing in `content_type`.
+    pub fn with_sniffing(mut self, enabled: bool) -> MimeRegistry {
+        self.sniffing = enabled;
+        self
+    }
+
+    /// Returns the media type registered for the extension of `path`, if any.
+    pub fn lookup(&self, path: &Path) -> Option<&str> {
+        let ext = path.extension()?.to_str()?;
+
+        self.types
+            .get(&Self::normalize_extension(ext))
+            .map(|mime| mime.as_str())
+    }
+
+    /// Determines the `Content-Type` to send for the file at `path` with the given leading `contents`.
+    ///
+    /// The extension lookup always wins. When it fails and sniffing is enabled, the contents are
+    /// inspected; otherwise `DEFAULT_MIME_TYPE` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::http::mime::MimeRegistry;
+    /// use std::path::Path;
+    ///
+    /// let registry = MimeRegistry::new();
+    /// assert_eq!(registry.content_type(Path::new("notes"), b"hello"), "application/octet-stream");
+    ///
+    /// let registry = registry.with_sniffing(true);
+    /// assert_eq!(registry.content_type(Path::new("notes"), b"hello"), "text/plain; charset=utf-8");
+    /// ```
+    pub fn content_type(&self, path: &Path, contents: &[u8]) -> String {
+        if let Some(mime) = self.lookup(path) {
+            return mime.to_string();
+        }
+
+        if self.sniffing {
+            if let Some(mime) = Self::sniff(contents) {
+                return mime.to_string();
+            }
+        }
+
+        DEFAULT_MIME_TYPE.to_string()
+    }
+
+    /// Guesses a media type from the first bytes of a file.
+    ///
+    /// Binary formats are recognised by their magic numbers. Otherwise, JSON documents are
+    /// detected by their leading characters, and any other valid UTF-8 without control characters,
+    /// including HTML, XML and SVG markup, is reported as plain text. Returns `None` if nothing
+    /// matches.
+    pub fn sniff(contents: &[u8]) -> Option<&'static str> {
+        if let Some((_, mime)) = SIGNATURES
+            .iter()
+            .find(|(signature, _)| contents.starts_with(signature))
+        {
+            return Some(mime);
+        }
+
+        if contents.len() >= 12 && &contents[0..4] == b"RIFF" && &contents[8..12] == b"WEBP" {
+            return Some("image/webp");
+        }
+
+        // Only sniff a prefix: the rest of the file may be cut in the middle of a character.
+        let prefix = &contents[..contents.len().min(512)];
+        let text = match std::str::from_utf8(prefix) {
+            Ok(text) => text,
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&prefix[..e.valid_up_to()]).ok()?
+            }
+            Err(_) => return None,
+        };
+
+        if text.is_empty()
+            || text
+                .chars()
+                .any(|c| c.is_control() && !c.is_ascii_whitespace())
+        {
+            return None;
+        }
+
+        let start = text.trim_start();
+        if start.starts_with('{') || start.starts_with('[') {
+            Some("application/json")
+        } else {
+            Some("text/plain; charset=utf-8")
+        }
+    }
+
+    fn normalize_extension(ext: &str) -> String {
+        ext.trim_start_matches('.').to_ascii_lowercase()
+    }
+}
+
+impl Default for MimeRegistry {
+    fn default() -> Self {
+        MimeRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_default_types() {
+        let registry = MimeRegistry::new();
+
+        assert_eq!(registry.lookup(Path::new("index.html")), Some("text/html; charset=utf-8"));
+        assert_eq!(registry.lookup(Path::new("app.js")), Some("text/javascript; charset=utf-8"));
+        assert_eq!(registry.lookup(Path::new("data.json")), Some("application/json"));
+        assert_eq!(registry.lookup(Path::new("img/photo.JPG")), Some("image/jpeg"));
+        assert_eq!(registry.lookup(Path::new("archive.tar.gz")), Some("application/gzip"));
+    }
+
+    #[test]
+    fn test_lookup_unknown_or_missing_extension() {
+        let registry = MimeRegistry::new();
+
+        assert_eq!(registry.lookup(Path::new("file.unknown")), None);
+        assert_eq!(registry.lookup(Path::new("Makefile")), None);
+        assert_eq!(registry.lookup(Path::new(".bashrc")), None);
+    }
+
+    #[test]
+    fn test_insert_overrides_and_adds() {
+        let mut registry = MimeRegistry::new();
+        registry.insert(".json", "application/vnd.api+json");
+        registry.insert("toml", "application/toml");
+
+        assert_eq!(registry.lookup(Path::new("a.json")), Some("application/vnd.api+json"));
+        assert_eq!(registry.lookup(Path::new("Cargo.toml")), Some("application/toml"));
+        assert_eq!(MimeRegistry::empty().lookup(Path::new("a.json")), None);
+    }
+
+    #[test]
+    fn test_content_type_prefers_extension_over_sniffing() {
+        let registry = MimeRegistry::new().with_sniffing(true);
+
+        assert_eq!(
+            registry.content_type(Path::new("data.txt"), b"{\"a\": 1}"),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            registry.content_type(Path::new("data"), b"{\"a\": 1}"),
+            "application/json"
+        );
+        assert_eq!(
+            registry.content_type(Path::new("page"), b"<html></html>"),
+            "text/plain; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn test_content_type_without_sniffing() {
+        let registry = MimeRegistry::new();
+
+        assert_eq!(
+            registry.content_type(Path::new("page"), b"<html></html>"),
+            DEFAULT_MIME_TYPE
+        );
+    }
+
+    #[test]
+    fn test_sniff_binary_signatures() {
+        assert_eq!(MimeRegistry::sniff(b"\x89PNG\r\n\x1a\nrest"), Some("image/png"));
+        assert_eq!(MimeRegistry::sniff(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(MimeRegistry::sniff(b"GIF89a...."), Some("image/gif"));
+        assert_eq!(MimeRegistry::sniff(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(MimeRegistry::sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+    }
+
+    #[test]
+    fn test_sniff_text_documents() {
+        for markup in [
+            &b"  <!DOCTYPE html><html>"[..],
+            b"<html><script>alert(1)</script>",
+            b"<?xml version=\"1.0\"?><svg xmlns=\"http://www.w3.org/2000/svg\"/>",
+            b"<svg onload=\"alert(1)\"/>",
+        ] {
+            assert_eq!(MimeRegistry::sniff(markup), Some("text/plain; charset=utf-8"));
+        }
+        assert_eq!(MimeRegistry::sniff(b"{\"a\": 1}"), Some("application/json"));
+        assert_eq!(
+            MimeRegistry::sniff("plain caf\u{e9}\n".as_bytes()),
+            Some("text/plain; charset=utf-8")
+        );
+    }
+
+    #[test]
+    fn test_sniff_unknown_binary() {
+        assert_eq!(MimeRegistry::sniff(b""), None);
+        assert_eq!(MimeRegistry::sniff(b"\x00\x01\x02\x03"), None);
+        assert_eq!(MimeRegistry::sniff(b"\xc3\x28 invalid utf-8"), None);
+    }
+}
@@ -1,3 +1,6 @@
+pub mod accept;
 pub mod code;
+pub mod date;
 pub mod header;
-pub mod method;
\ No newline at end of file
+pub mod method;
+pub mod mime;
@@ -1,5 +1,6 @@
 pub mod http;
 pub mod request;
 pub mod response;
+pub mod router;
 pub mod url;
 pub mod thread_pool;
\ No newline at end of file
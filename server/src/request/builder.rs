@@ -1,10 +1,121 @@
-use crate::{http::method::HttpMethod, url::Url};
+use crate::{
+    http::{code::HttpCode, method::HttpMethod},
+    router::state::{MissingStateError, StateMap},
+    url::Url,
+};
 
 use anyhow::{anyhow, Result};
-use std::{io::Read, net::TcpStream, str::FromStr};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::Read,
+    net::TcpStream,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// The ID of the next request read by `Request::new`.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The HTTP versions the server speaks. Responses are written in the version of the request, so
+/// any other version is refused rather than echoed back in the status line.
+pub const SUPPORTED_VERSIONS: [&str; 2] = ["HTTP/1.0", "HTTP/1.1"];
+
+/// Returned by `Request::new` when the request line names an HTTP version the server does not
+/// speak. It should be answered with `505 HTTP Version Not Supported`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnsupportedVersionError {
+    /// The version token of the request line.
+    pub version: String,
+}
+
+impl Display for UnsupportedVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unsupported HTTP version {:?}", self.version)
+    }
+}
+
+impl Error for UnsupportedVersionError {}
+
+/// The largest request body `Request::new` accepts, in bytes.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// The largest request head, from the request line to the blank line ending the headers, that
+/// `Request::new` reads, in bytes.
+pub const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// How many bytes are read from the stream at a time.
+const READ_SIZE: usize = 1024;
+
+/// Returned by `Request::new` when the head of the request is larger than `MAX_HEAD_SIZE`. It
+/// should be answered with `431 Request Header Fields Too Large`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HeadTooLargeError {
+    /// The largest head accepted.
+    pub limit: usize,
+}
+
+impl Display for HeadTooLargeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request head exceeds the limit of {} bytes", self.limit)
+    }
+}
+
+impl Error for HeadTooLargeError {}
+
+/// Returned by `Request::new` when the body announced by `Content-Length` cannot be read.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BodyError {
+    /// The body is larger than the limit, and was not read.
+    TooLarge {
+        /// The announced length of the body.
+        length: usize,
+        /// The largest body accepted.
+        limit: usize,
+    },
+
+    /// The connection ended before the whole body arrived.
+    Incomplete {
+        /// The announced length of the body.
+        length: usize,
+        /// The number of bytes received.
+        received: usize,
+    },
+}
+
+impl BodyError {
+    /// Returns the status code the request should be answered with: `413 Content Too Large` or
+    /// `400 Bad Request`.
+    pub fn code(&self) -> HttpCode {
+        match self {
+            BodyError::TooLarge { .. } => HttpCode::ContentTooLarge,
+            BodyError::Incomplete { .. } => HttpCode::BadRequest,
+        }
+    }
+}
+
+impl Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::TooLarge { length, limit } => {
+                write!(f, "Request body of {} bytes exceeds the limit of {} bytes", length, limit)
+            }
+            BodyError::Incomplete { length, received } => {
+                write!(f, "Request body ended after {} of {} bytes", received, length)
+            }
+        }
+    }
+}
 
-#[derive(Debug)]
+impl Error for BodyError {}
+
+#[derive(Debug, Default)]
 pub struct Request {
+    /// Identifies the request in logs. IDs are unique within a process and start at 1.
+    pub id: u64,
     pub request: Vec<String>,
     pub user_agent: Option<String>,
     pub accept_encoding: Option<String>,
@@ -12,14 +123,33 @@ pub struct Request {
     pub method: Option<HttpMethod>,
     pub url: Option<Url>,
     pub http_version: String,
+    pub body: Vec<u8>,
+    pub state: StateMap,
 }
 
 impl Request {
+    /// Reads a request from `stream`, accepting bodies of up to `DEFAULT_MAX_BODY_SIZE` bytes.
+    ///
+    /// See `Request::with_max_body_size`.
     pub fn new(stream: &mut TcpStream) -> Result<Request> {
-        let mut buffer = [0; 1024];
-        let bytes_read = stream.read(&mut buffer)?;
+        Self::with_max_body_size(stream, DEFAULT_MAX_BODY_SIZE)
+    }
 
-        let request = Self::get_request(&buffer, bytes_read)?;
+    /// Reads a request from `stream`: the head, then the whole body announced by its
+    /// `Content-Length` header.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Request)`: The parsed request.
+    /// - `Err(HeadTooLargeError)`: If the head is larger than `MAX_HEAD_SIZE`.
+    /// - `Err(BodyError)`: If the body is larger than `max_body_size`, or the connection ended
+    ///   before all of it arrived.
+    /// - `Err(UnsupportedVersionError)`: If the request is not HTTP/1.0 or HTTP/1.1.
+    /// - `Err(PercentDecodeError)`: If the URL is not validly percent-encoded.
+    pub fn with_max_body_size(stream: &mut TcpStream, max_body_size: usize) -> Result<Request> {
+        let (head, rest) = Self::read_head(stream)?;
+
+        let mut request = Self::get_request(&head)?;
         let request_line = request[0].to_string();
 
         let user_agent = Self::get_user_agent_header(&request);
@@ -27,10 +157,20 @@ impl Request {
         let content_length = Self::get_content_length(&request);
         let method = Self::get_method(&request_line);
         let url_str = Self::get_url(&request_line);
-        let url = url_str.as_ref().map(|s| Url::new(s.as_str()));
-        let http_version = Self::get_http_version(&request_line);
+        let url = url_str.as_deref().map(Url::new).transpose()?;
+        let http_version = Self::get_http_version(&request_line)?;
+        let body = Self::read_body(stream, rest, content_length, max_body_size)?;
+
+        // The raw lines go on past the blank line ending the head, with the lines of the body.
+        let body_text = String::from_utf8_lossy(&body);
+        let body_text = body_text.trim_end();
+        if !body_text.is_empty() {
+            request.push(String::new());
+            request.extend(body_text.split("\r\n").map(|s| s.to_string()));
+        }
 
         Ok(Request {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
             request,
             user_agent,
             accept_encoding,
@@ -38,11 +178,107 @@ impl Request {
             method,
             url,
             http_version,
+            body,
+            state: StateMap::new(),
         })
     }
 
-    fn get_request(buffer: &[u8], bytes_read: usize) -> Result<Vec<String>> {
-        let request_str = String::from_utf8_lossy(&buffer[..bytes_read]).trim().to_string();
+    /// Returns the value of the header `name`, compared case-insensitively.
+    ///
+    /// Only the header section is searched, and surrounding whitespace is trimmed from the value.
+    /// If the header appears several times, the first value is returned.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.request
+            .iter()
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    /// Returns the application state of type `T` registered with `Router::with_state`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Arc<T>)`: The shared state.
+    /// - `Err(MissingStateError)`: If no state of type `T` was registered on the router.
+    pub fn state<T: Send + Sync + 'static>(&self) -> Result<Arc<T>, MissingStateError> {
+        self.state.get::<T>()
+    }
+
+    /// Reads from `stream` until the blank line ending the head, and returns the head along with
+    /// the bytes of the body read past it.
+    ///
+    /// If the stream ends first, whatever arrived is the head. Reading stops with an error once
+    /// more than `MAX_HEAD_SIZE` bytes arrived without a blank line.
+    fn read_head(stream: &mut impl Read) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0; READ_SIZE];
+
+        loop {
+            let searched = buffer.len().saturating_sub(3);
+            let bytes_read = stream.read(&mut chunk)?;
+            buffer.extend_from_slice(&chunk[..bytes_read]);
+
+            if let Some(end) = buffer[searched..].windows(4).position(|w| w == b"\r\n\r\n") {
+                if searched + end + 4 > MAX_HEAD_SIZE {
+                    break;
+                }
+                let rest = buffer.split_off(searched + end + 4);
+                return Ok((buffer, rest));
+            }
+            if buffer.len() > MAX_HEAD_SIZE {
+                break;
+            }
+            if bytes_read == 0 {
+                return Ok((buffer, Vec::new()));
+            }
+        }
+
+        Err(HeadTooLargeError {
+            limit: MAX_HEAD_SIZE,
+        }
+        .into())
+    }
+
+    /// Reads the rest of the body from `stream`, after the bytes of it already read in `body`.
+    ///
+    /// Without `Content-Length`, the body is what was read along with the head.
+    fn read_body(
+        stream: &mut impl Read,
+        mut body: Vec<u8>,
+        content_length: Option<usize>,
+        max_body_size: usize,
+    ) -> Result<Vec<u8>, BodyError> {
+        let Some(length) = content_length else {
+            return Ok(body);
+        };
+        if length > max_body_size {
+            return Err(BodyError::TooLarge {
+                length,
+                limit: max_body_size,
+            });
+        }
+
+        if body.len() < length {
+            let missing = (length - body.len()) as u64;
+            // A read error ends the body like the end of the stream: it is incomplete either way.
+            let _ = stream.take(missing).read_to_end(&mut body);
+        }
+        if body.len() < length {
+            return Err(BodyError::Incomplete {
+                length,
+                received: body.len(),
+            });
+        }
+        body.truncate(length);
+
+        Ok(body)
+    }
+
+    fn get_request(head: &[u8]) -> Result<Vec<String>> {
+        let request_str = String::from_utf8_lossy(head).trim().to_string();
 
         if request_str.is_empty() {
             Err(anyhow!("Error: request is empty"))
@@ -56,22 +292,22 @@ impl Request {
         }
     }
 
-    fn get_user_agent_header(request: &Vec<String>) -> Option<String> {
+    fn get_user_agent_header(request: &[String]) -> Option<String> {
         request
             .iter()
             .find(|line| line.contains("User-Agent:"))
-            .and_then(|result| Some(result.replace("User-Agent: ", "")))
+            .map(|result| result.replace("User-Agent: ", ""))
     }
 
-    fn get_accept_encoding(request: &Vec<String>) -> Option<String> {
+    fn get_accept_encoding(request: &[String]) -> Option<String> {
         request
             .iter()
             .find(|x| x.contains("Accept-Encoding"))
             .and_then(|ac| ac.split("Accept-Encoding: ").nth(1))
-            .and_then(|e| Some(e.to_string()))
+            .map(|e| e.to_string())
     }
 
-    fn get_content_length(request: &Vec<String>) -> Option<usize> {
+    fn get_content_length(request: &[String]) -> Option<usize> {
         request
             .iter()
             .find(|x| x.contains("Content-Length"))
@@ -79,33 +315,35 @@ impl Request {
             .and_then(|cl| cl.parse::<usize>().ok())
     }
 
-    fn get_method(request_line: &String) -> Option<HttpMethod> {
+    fn get_method(request_line: &str) -> Option<HttpMethod> {
         request_line
             .split(" ")
             .nth(0)
             .and_then(|s| HttpMethod::from_str(s).ok())
     }
 
-    fn get_url(request_line: &String) -> Option<String> {
+    fn get_url(request_line: &str) -> Option<String> {
         request_line
             .split(" ")
             .nth(1)
-            .and_then(|s| Some(s.to_string()))
+            .map(|s| s.to_string())
     }
 
-    fn get_http_version(request_line: &String) -> String {
-        request_line
-            .split(" ")
-            .nth(3)
-            .and_then(|s| Some(s.to_string()))
-            .get_or_insert_with(|| "HTTP/1.1".to_string())
-            .to_string()
+    fn get_http_version(request_line: &str) -> Result<String, UnsupportedVersionError> {
+        match request_line.split(" ").nth(2) {
+            Some(version) if SUPPORTED_VERSIONS.contains(&version) => Ok(version.to_string()),
+            Some(version) => Err(UnsupportedVersionError {
+                version: version.to_string(),
+            }),
+            None => Ok("HTTP/1.1".to_string()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::url::percent::PercentDecodeError;
     use std::io::Write;
     use std::net::{TcpListener, TcpStream};
     use anyhow::Result;
@@ -134,7 +372,7 @@ mod tests {
         let request = Request::new(&mut client_stream).unwrap();
 
         assert_eq!(request.method, Some(HttpMethod::GET));
-        assert_eq!(request.url, Some(Url::new("/home")));
+        assert_eq!(request.url, Some(Url::new("/home").unwrap()));
         assert_eq!(request.http_version, "HTTP/1.1".to_string());
         assert_eq!(request.user_agent, Some("TestAgent/1.0".to_string()));
         assert_eq!(request.accept_encoding, Some("gzip, deflate".to_string()));
@@ -147,6 +385,17 @@ mod tests {
         assert_eq!(request.request[4], "Content-Length: 0");
     }
 
+    #[test]
+    fn test_new_requests_get_distinct_ids() {
+        let (mut first, _) = create_stream("GET / HTTP/1.1\r\n\r\n").unwrap();
+        let (mut second, _) = create_stream("GET / HTTP/1.1\r\n\r\n").unwrap();
+        let first = Request::new(&mut first).unwrap();
+        let second = Request::new(&mut second).unwrap();
+
+        assert!(first.id > 0);
+        assert_ne!(first.id, second.id);
+    }
+
     #[test]
     fn test_new_valid_post_request_with_body() {
         let raw_request = "POST /submit HTTP/1.1\r\n\
@@ -160,7 +409,7 @@ mod tests {
         let request = Request::new(&mut client_stream).unwrap();
 
         assert_eq!(request.method, Some(HttpMethod::POST));
-        assert_eq!(request.url, Some(Url::new("/submit")));
+        assert_eq!(request.url, Some(Url::new("/submit").unwrap()));
         assert_eq!(request.http_version, "HTTP/1.1".to_string());
         assert_eq!(request.user_agent, Some("TestAgent/2.0".to_string()));
         assert_eq!(request.accept_encoding, Some("br".to_string()));
@@ -171,7 +420,8 @@ mod tests {
         assert_eq!(request.request[2], "User-Agent: TestAgent/2.0");
         assert_eq!(request.request[3], "Accept-Encoding: br");
         assert_eq!(request.request[4], "Content-Length: 27");
-        assert_eq!(request.request[6].contains("field1=value1&field2=value2"), true);
+        assert!(request.request[6].contains("field1=value1&field2=value2"));
+        assert_eq!(request.body, b"field1=value1&field2=value2");
     }
 
     #[test]
@@ -183,7 +433,7 @@ mod tests {
         let request = Request::new(&mut client_stream).unwrap();
 
         assert_eq!(request.method, Some(HttpMethod::DELETE));
-        assert_eq!(request.url, Some(Url::new("/resource/123")));
+        assert_eq!(request.url, Some(Url::new("/resource/123").unwrap()));
         assert_eq!(request.http_version, "HTTP/1.1".to_string());
         assert_eq!(request.user_agent, None);
         assert_eq!(request.accept_encoding, None);
@@ -193,6 +443,30 @@ mod tests {
         assert_eq!(request.request[1], "Host: api.example.com");
     }
 
+    #[test]
+    fn test_new_request_http_version() {
+        let (mut client_stream, _) = create_stream("GET / HTTP/1.0\r\n\r\n").unwrap();
+        let request = Request::new(&mut client_stream).unwrap();
+
+        assert_eq!(request.http_version, "HTTP/1.0");
+    }
+
+    #[test]
+    fn test_new_request_unsupported_http_version() {
+        for version in ["HTTP/2.0", "HTTP/1.1\x1b[31m", "http/1.1", "<script>"] {
+            let raw_request = format!("GET / {}\r\nHost: localhost\r\n\r\n", version);
+            let (mut client_stream, _) = create_stream(&raw_request).unwrap();
+            let error = Request::new(&mut client_stream).unwrap_err();
+
+            assert_eq!(
+                error.downcast_ref::<UnsupportedVersionError>(),
+                Some(&UnsupportedVersionError {
+                    version: version.to_string()
+                })
+            );
+        }
+    }
+
     #[test]
     fn test_new_request_invalid_method() {
         let raw_request = "FETCH /data HTTP/1.1\r\n\
@@ -202,7 +476,7 @@ mod tests {
         let request = Request::new(&mut client_stream).unwrap();
 
         assert_eq!(request.method, None);
-        assert_eq!(request.url, Some(Url::new("/data")));
+        assert_eq!(request.url, Some(Url::new("/data").unwrap()));
         assert_eq!(request.http_version, "HTTP/1.1".to_string());
         assert_eq!(request.user_agent, None);
         assert_eq!(request.accept_encoding, None);
@@ -251,17 +525,19 @@ mod tests {
                            Accept-Encoding: gzip\r\n\
                            Accept-Encoding: br\r\n\
                            Content-Length: 50\r\n\r\n";
+        let raw_request = format!("{}{}", raw_request, "x".repeat(50));
 
-        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let (mut client_stream, _) = create_stream(&raw_request).unwrap();
         let request = Request::new(&mut client_stream).unwrap();
 
         assert_eq!(request.method, Some(HttpMethod::GET));
-        assert_eq!(request.url, Some(Url::new("/multi")));
+        assert_eq!(request.url, Some(Url::new("/multi").unwrap()));
         assert_eq!(request.http_version, "HTTP/1.1".to_string());
         assert_eq!(request.user_agent, Some("TestAgent/1.0".to_string()));
         assert_eq!(request.accept_encoding, Some("gzip".to_string()));
         assert_eq!(request.content_length, Some(50));
-        assert_eq!(request.request.len(), 7);
+        assert_eq!(request.body.len(), 50);
+        assert_eq!(request.request.len(), 9);
         assert_eq!(request.request[0], "GET /multi HTTP/1.1");
         assert_eq!(request.request[1], "Host: localhost");
         assert_eq!(request.request[2], "User-Agent: TestAgent/1.0");
@@ -270,4 +546,135 @@ mod tests {
         assert_eq!(request.request[5], "Accept-Encoding: br");
         assert_eq!(request.request[6], "Content-Length: 50");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_new_request_invalid_percent_encoding() {
+        let raw_request = "GET /echo/100% HTTP/1.1\r\n\
+                           Host: localhost\r\n\r\n";
+
+        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let result = Request::new(&mut client_stream);
+        assert!(result.is_err());
+        let error = result.unwrap_err();
+        assert!(error.downcast_ref::<PercentDecodeError>().is_some());
+    }
+
+    #[test]
+    fn test_body_is_limited_to_content_length() {
+        let raw_request = "POST /submit HTTP/1.1\r\n\
+                           Content-Length: 5\r\n\r\n\
+                           hello\r\n\r\nworld";
+
+        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let request = Request::new(&mut client_stream).unwrap();
+
+        assert_eq!(request.body, b"hello");
+    }
+
+    #[test]
+    fn test_body_is_read_in_full() {
+        let body = "x".repeat(10_000);
+        let raw_request = format!("POST /upload HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+
+        let (mut client_stream, _) = create_stream(&raw_request).unwrap();
+        let request = Request::new(&mut client_stream).unwrap();
+
+        assert_eq!(request.body, body.as_bytes());
+        assert_eq!(request.request.len(), 4);
+        assert_eq!(request.request[3], body);
+    }
+
+    #[test]
+    fn test_body_errors() {
+        let raw_request = "POST /upload HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort";
+        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let error = Request::new(&mut client_stream).unwrap_err();
+        let error = error.downcast_ref::<BodyError>().unwrap();
+        assert_eq!(error, &BodyError::Incomplete { length: 10, received: 5 });
+        assert_eq!(error.code(), HttpCode::BadRequest);
+
+        let raw_request = "POST /upload HTTP/1.1\r\nContent-Length: 11\r\n\r\nhello world";
+        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let error = Request::with_max_body_size(&mut client_stream, 10).unwrap_err();
+        let error = error.downcast_ref::<BodyError>().unwrap();
+        assert_eq!(error, &BodyError::TooLarge { length: 11, limit: 10 });
+        assert_eq!(error.code(), HttpCode::ContentTooLarge);
+    }
+
+    #[test]
+    fn test_head_over_limit() {
+        let raw_request = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(MAX_HEAD_SIZE));
+        let (mut client_stream, _server) = create_stream(&raw_request).unwrap();
+        let error = Request::new(&mut client_stream).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<HeadTooLargeError>(),
+            Some(&HeadTooLargeError { limit: MAX_HEAD_SIZE })
+        );
+
+        // The connection stays open, but reading stops at the limit.
+        let raw_request = format!("GET / HTTP/1.1\r\nX-Padding: {}", "x".repeat(2 * MAX_HEAD_SIZE));
+        let (mut client_stream, _server) = create_stream(&raw_request).unwrap();
+        let error = Request::new(&mut client_stream).unwrap_err();
+        assert!(error.downcast_ref::<HeadTooLargeError>().is_some());
+
+        let raw_request = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(MAX_HEAD_SIZE - 40));
+        let (mut client_stream, _) = create_stream(&raw_request).unwrap();
+        assert!(Request::new(&mut client_stream).is_ok());
+    }
+
+    #[test]
+    fn test_eof_before_blank_line() {
+        let (mut client_stream, _) = create_stream("GET /a HTTP/1.1\r\nHost: localhost").unwrap();
+        let request = Request::new(&mut client_stream).unwrap();
+
+        assert_eq!(request.url, Some(Url::new("/a").unwrap()));
+        assert_eq!(request.header("Host"), Some("localhost"));
+        assert!(request.body.is_empty());
+
+        let raw_request = "POST /a HTTP/1.1\r\nContent-Length: 3";
+        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let error = Request::new(&mut client_stream).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<BodyError>(),
+            Some(&BodyError::Incomplete { length: 3, received: 0 })
+        );
+    }
+
+    #[test]
+    fn test_head_split_across_reads() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        let writer = std::thread::spawn(move || {
+            for part in ["GET /a HTTP/1.1\r\nContent-", "Length: 2\r\n\r", "\n", "o", "k"] {
+                server.write_all(part.as_bytes()).unwrap();
+                server.flush().unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        });
+        let request = Request::new(&mut client).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(request.content_length, Some(2));
+        assert_eq!(request.body, b"ok");
+    }
+
+    #[test]
+    fn test_header_lookup() {
+        let raw_request = "POST /submit HTTP/1.1\r\n\
+                           content-type:  application/json \r\n\
+                           X-Token: a:b\r\n\
+                           X-Token: c\r\n\r\n\
+                           X-Body: not a header";
+
+        let (mut client_stream, _) = create_stream(raw_request).unwrap();
+        let request = Request::new(&mut client_stream).unwrap();
+
+        assert_eq!(request.header("Content-Type"), Some("application/json"));
+        assert_eq!(request.header("x-token"), Some("a:b"));
+        assert_eq!(request.header("X-Body"), None);
+        assert_eq!(request.header("Host"), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! # Body Module
+//!
+//! This module defines the `Body` enum, which holds the payload of a `Response`.
+//!
+//! A body is either empty, held in memory as bytes or text, or streamed from any `Read`
+//! implementation, so large payloads do not have to be loaded into memory first. Streams of a
+//! known length, such as files, are sent with a `Content-Length` header; other streams are sent
+//! with the chunked transfer coding.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::response::Body;
+//!
+//! let text = Body::from("Hello!");
+//! assert_eq!(text.len(), Some(6));
+//!
+//! let bytes = Body::from(vec![0u8, 1, 2]);
+//! assert_eq!(bytes.as_bytes(), Some(&[0u8, 1, 2][..]));
+//!
+//! let stream = Body::Stream(Box::new(std::io::empty()));
+//! assert_eq!(stream.len(), None);
+//!
+//! let sized = Body::SizedStream(Box::new(&b"abc"[..]), 3);
+//! assert_eq!(sized.len(), Some(3));
+//! assert_eq!(sized.as_bytes(), None);
+//! ```
+
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, Read, Write},
+};
+
+use super::stream::copy_chunks;
+
+/// The payload of a response.
+#[derive(Default)]
+pub enum Body {
+    /// No payload.
+    #[default]
+    Empty,
+
+    /// A binary payload held in memory.
+    Bytes(Vec<u8>),
+
+    /// A text payload held in memory.
+    Text(String),
+
+    /// A payload read from a source until it is exhausted.
+    Stream(Box<dyn Read + Send>),
+
+    /// A payload of the given length in bytes, read from a source.
+    SizedStream(Box<dyn Read + Send>, usize),
+}
+
+impl Body {
+    /// Creates a body streaming the contents of `file`, with its current size as the length.
+    pub fn file(file: File) -> io::Result<Body> {
+        let len = file.metadata()?.len();
+        let len = usize::try_from(len).map_err(|e| io::Error::new(io::ErrorKind::Unsupported, e))?;
+
+        Ok(Body::SizedStream(Box::new(file), len))
+    }
+
+    /// Returns the length of the payload in bytes, or `None` for a stream of unknown length.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Body::SizedStream(_, len) => Some(*len),
+            _ => self.as_bytes().map(|bytes| bytes.len()),
+        }
+    }
+
+    /// Returns `true` if the body is known to have no payload.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Returns the payload, unless it is a stream.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Empty => Some(&[]),
+            Body::Bytes(bytes) => Some(bytes),
+            Body::Text(text) => Some(text.as_bytes()),
+            Body::Stream(_) | Body::SizedStream(..) => None,
+        }
+    }
+
+    /// Writes the payload to `writer`, reading streams in chunks; see the `stream` module.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())`: The whole payload was written.
+    /// - `Err(io::Error)`: If writing fails, reading a stream fails, or a sized stream ends early.
+    pub fn write_to(self, writer: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Body::Empty => Ok(()),
+            Body::Bytes(bytes) => writer.write_all(&bytes),
+            Body::Text(text) => writer.write_all(text.as_bytes()),
+            Body::Stream(mut reader) => copy_chunks(&mut reader, writer, None).map(|_| ()),
+            Body::SizedStream(mut reader, len) => {
+                copy_chunks(&mut reader, writer, Some(len)).map(|_| ())
+            }
+        }
+    }
+}
+
+impl Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Body::Empty => write!(f, "Empty"),
+            Body::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            Body::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            Body::Stream(_) => write!(f, "Stream"),
+            Body::SizedStream(_, len) => f.debug_tuple("SizedStream").field(len).finish(),
+        }
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Self {
+        Body::Text(text.to_string())
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Body::Text(text)
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::Bytes(bytes)
+    }
+}
+
+impl From<Option<String>> for Body {
+    fn from(text: Option<String>) -> Self {
+        text.map_or(Body::Empty, Body::Text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_len_and_bytes() {
+        assert_eq!(Body::Empty.len(), Some(0));
+        assert!(Body::Empty.is_empty());
+        assert_eq!(Body::from("héllo").len(), Some(6));
+        assert_eq!(Body::from(vec![1, 2]).as_bytes(), Some(&[1u8, 2][..]));
+        assert_eq!(Body::Stream(Box::new(&b"abc"[..])).as_bytes(), None);
+        assert!(!Body::Stream(Box::new(&b""[..])).is_empty());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut output = Vec::new();
+        Body::from("text").write_to(&mut output).unwrap();
+        Body::Stream(Box::new(&b" stream"[..])).write_to(&mut output).unwrap();
+        Body::SizedStream(Box::new(&b" sized!"[..]), 6).write_to(&mut output).unwrap();
+        assert_eq!(output, b"text stream sized");
+
+        let error = Body::SizedStream(Box::new(&b"ab"[..]), 3)
+            .write_to(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_file() {
+        let path = std::env::temp_dir().join(format!("body-test-{}", std::process::id()));
+        std::fs::write(&path, b"file contents").unwrap();
+
+        let body = Body::file(File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(body.len(), Some(13));
+        let mut output = Vec::new();
+        body.write_to(&mut output).unwrap();
+        assert_eq!(output, b"file contents");
+    }
+
+    #[test]
+    fn test_from_option() {
+        assert!(matches!(Body::from(None::<String>), Body::Empty));
+        assert!(matches!(Body::from(Some("a".to_string())), Body::Text(t) if t == "a"));
+    }
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(format!("{:?}", Body::from(vec![0; 3])), "Bytes(3)");
+        assert_eq!(format!("{:?}", Body::from("a")), "Text(\"a\")");
+        assert_eq!(format!("{:?}", Body::Stream(Box::new(std::io::empty()))), "Stream");
+        assert_eq!(
+            format!("{:?}", Body::SizedStream(Box::new(std::io::empty()), 0)),
+            "SizedStream(0)"
+        );
+    }
+}
@@ -1,40 +1,350 @@
-use std::fmt::Display;
+//! # Response Module
+//!
+//! This module defines the `Response` struct, and `ResponseBuilder` to create one fluently.
+//!
+//! The builder fills in the status text and a `Content-Length` header matching the body (or
+//! `Transfer-Encoding: chunked` for streams of unknown length), so handlers only have to state
+//! what is specific to their response. The HTTP version is taken from the request by the router,
+//! after the handler returns.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use serde_json::json;
+//! use server::{
+//!     http::{code::HttpCode, header::HttpHeader},
+//!     response::Response,
+//! };
+//!
+//! let response = Response::ok()
+//!     .header(HttpHeader::ContentType("text/plain".to_string()))
+//!     .body("Hello!")
+//!     .build();
+//! assert_eq!(
+//!     response.to_string(),
+//!     "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 6\r\n\r\nHello!"
+//! );
+//!
+//! let response = Response::builder(HttpCode::Created)
+//!     .json(&json!({ "id": 42 }))
+//!     .unwrap()
+//!     .build();
+//! assert_eq!(response.body.as_bytes(), Some(&b"{\"id\":42}"[..]));
+//!
+//! let response = Response::builder(HttpCode::Found).redirect("/login").build();
+//! assert_eq!(
+//!     response.head(),
+//!     "HTTP/1.1 302 Found\r\nLocation: /login\r\nContent-Length: 0\r\n\r\n"
+//! );
+//! ```
 
-use crate::http::{code::HttpCode, header::HttpHeader};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    io::{self, Write},
+};
 
+use serde::Serialize;
+
+use crate::{
+    http::{code::HttpCode, header::HttpHeader},
+    request::{builder::SUPPORTED_VERSIONS, Request},
+};
+
+use super::{
+    body::Body,
+    into_response::IntoResponse,
+    redirect::{resolve_location, RedirectKind},
+    stream::{write_all_vectored, ChunkedWriter, Trailers},
+};
+
+/// Heads larger than this are not kept in `HEAD_BUFFER` once written.
+const MAX_REUSED_HEAD: usize = 16 * 1024;
+
+thread_local! {
+    /// The buffer each worker thread serializes response heads into, reused across responses.
+    static HEAD_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug)]
 pub struct Response {
     pub status_code: HttpCode,
     pub status_text: String,
     pub http_version: String,
     pub headers: Option<Vec<HttpHeader>>,
-    pub body: Option<String>,
+    pub body: Body,
+
+    /// Fields sent after a chunked body, if the client accepts them.
+    pub trailers: Option<Trailers>,
+}
+
+impl Response {
+    /// Starts building a response with `status_code`.
+    pub fn builder(status_code: HttpCode) -> ResponseBuilder {
+        ResponseBuilder {
+            status_code,
+            headers: Vec::new(),
+            body: Body::Empty,
+            trailers: None,
+        }
+    }
+
+    /// Starts building a `200 OK` response.
+    pub fn ok() -> ResponseBuilder {
+        Response::builder(HttpCode::Ok)
+    }
+
+    /// Starts building a `201 Created` response.
+    pub fn created() -> ResponseBuilder {
+        Response::builder(HttpCode::Created)
+    }
+
+    /// Starts building a `404 Not Found` response.
+    pub fn not_found() -> ResponseBuilder {
+        Response::builder(HttpCode::NotFound)
+    }
+
+    /// Starts building a redirect to `target`, with the status code of `kind`.
+    ///
+    /// A relative `target` is resolved against the request URL by `prepare_for`.
+    pub fn redirect(target: &str, kind: RedirectKind) -> ResponseBuilder {
+        Response::builder(kind.code()).redirect(target)
+    }
+
+    /// Returns the status line and headers, terminated by the empty line preceding the body.
+    pub fn head(&self) -> String {
+        let mut head = Vec::new();
+        self.write_head(&mut head);
+
+        String::from_utf8_lossy(&head).into_owned()
+    }
+
+    /// Appends the status line and headers, terminated by the empty line, to `buffer`.
+    fn write_head(&self, buffer: &mut Vec<u8>) {
+        // Writing to a `Vec` cannot fail.
+        let _ = write!(
+            buffer,
+            "{} {} {}\r\n",
+            self.http_version,
+            self.status_code.to_u16(),
+            self.status_text
+        );
+        for header in self.headers.iter().flatten() {
+            let _ = write!(buffer, "{}\r\n", header);
+        }
+        buffer.extend_from_slice(b"\r\n");
+    }
+
+    /// Returns `true` if the body is sent with the chunked transfer coding.
+    pub fn is_chunked(&self) -> bool {
+        self.headers.iter().flatten().any(|header| {
+            matches!(header, HttpHeader::TransferEncoding(codings)
+                if codings.rsplit(',').next().is_some_and(|c| c.trim().eq_ignore_ascii_case("chunked")))
+        })
+    }
+
+    /// Adapts the response to the client that sent `request`. The router calls this for every
+    /// response it returns.
+    ///
+    /// - The response takes the HTTP version of the request, if it is one the server supports;
+    ///   any other version is never echoed back in the status line.
+    /// - Trailers, and the `Trailer` header announcing them, are dropped unless the client sent
+    ///   `TE: trailers`.
+    /// - HTTP/1.0 clients do not understand the chunked transfer coding, so chunked bodies are
+    ///   sent as-is with `Connection: close` instead: closing the connection ends the body.
+    /// - A relative `Location` is resolved against the request URL; see `resolve_location`.
+    pub fn prepare_for(&mut self, request: &Request) {
+        if SUPPORTED_VERSIONS.contains(&request.http_version.as_str()) {
+            self.http_version = request.http_version.clone();
+        }
+
+        if let Some(url) = &request.url {
+            for header in self.headers.iter_mut().flatten() {
+                if let HttpHeader::Location(location) = header {
+                    *location = resolve_location(url, location);
+                }
+            }
+        }
+
+        if self.http_version == "HTTP/1.0" && self.is_chunked() {
+            let headers = self.headers.get_or_insert_with(Vec::new);
+            headers.retain(|header| !matches!(header, HttpHeader::TransferEncoding(_)));
+            headers.push(HttpHeader::Connection("close".to_string()));
+        }
+
+        let accepts_trailers = request.header("TE").is_some_and(|te| {
+            te.split(',')
+                .filter_map(|coding| coding.split(';').next())
+                .any(|coding| coding.trim().eq_ignore_ascii_case("trailers"))
+        });
+        if !accepts_trailers || !self.is_chunked() {
+            self.trailers = None;
+            if let Some(headers) = self.headers.as_mut() {
+                headers.retain(|header| !matches!(header, HttpHeader::Trailer(_)));
+            }
+        }
+    }
+
+    /// Writes the whole response to `writer`: the head, then the body.
+    ///
+    /// Streamed bodies are copied in chunks as they are read; see the `stream` module. If the
+    /// response `is_chunked`, the body is sent with the chunked transfer coding, followed by the
+    /// trailers. The head is serialized into a per-thread buffer that later responses reuse.
+    pub fn write_to(self, writer: &mut impl Write) -> io::Result<()> {
+        let mut head = HEAD_BUFFER.take();
+        head.clear();
+        self.write_head(&mut head);
+
+        let result = self.send(writer, &head);
+        if head.capacity() <= MAX_REUSED_HEAD {
+            HEAD_BUFFER.set(head);
+        }
+
+        result
+    }
+
+    /// Writes `head` followed by the body.
+    ///
+    /// In-memory bodies are sent together with the head in one vectored write, so a small
+    /// response leaves in a single packet instead of waiting on Nagle's algorithm between two.
+    fn send(self, writer: &mut impl Write, head: &[u8]) -> io::Result<()> {
+        if !self.is_chunked() {
+            if let Some(body) = self.body.as_bytes() {
+                write_all_vectored(writer, [head, body])?;
+
+                return writer.flush();
+            }
+        }
+
+        writer.write_all(head)?;
+
+        if self.is_chunked() {
+            let mut chunked = ChunkedWriter::new(&mut *writer);
+            self.body.write_to(&mut chunked)?;
+
+            let trailers = self.trailers.map(Trailers::compute).unwrap_or_default();
+            chunked.finish_with_trailers(&trailers)?;
+        } else {
+            self.body.write_to(writer)?;
+        }
+
+        writer.flush()
+    }
 }
 
 impl Display for Response {
+    /// Formats the whole response as text.
+    ///
+    /// Binary bodies are decoded lossily and streamed bodies are left out, so this is meant for
+    /// logging and tests; connections are written with `write_to`.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut headers = String::new();
-        let mut body = String::from("\r\n\r\n");
+        write!(f, "{}", self.head())?;
 
-        if let Some(h) = &self.headers {
-            headers = h
-                .iter()
-                .map(|h| format!("\r\n{}", h.to_string()))
-                .collect::<String>();
+        if let Some(body) = self.body.as_bytes() {
+            write!(f, "{}", String::from_utf8_lossy(body))?;
         }
 
-        if let Some(b) = &self.body {
-            body = format!("\r\n\r\n{}", b);
+        Ok(())
+    }
+}
+
+/// Builds a `Response`; see the module documentation.
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    status_code: HttpCode,
+    headers: Vec<HttpHeader>,
+    body: Body,
+    trailers: Option<Trailers>,
+}
+
+impl ResponseBuilder {
+    /// Adds `header` to the response.
+    ///
+    /// `Content-Length` headers are ignored, since `build` computes the length from the body.
+    pub fn header(mut self, header: HttpHeader) -> ResponseBuilder {
+        if !matches!(header, HttpHeader::ContentLength(_)) {
+            self.headers.push(header);
         }
 
-        write!(
-            f,
-            "{} {} {}{}{}",
-            &self.http_version,
-            &self.status_code.to_u16(),
-            &self.status_text,
-            &headers,
-            &body
-        )
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn body(mut self, body: impl Into<Body>) -> ResponseBuilder {
+        self.body = body.into();
+
+        self
+    }
+
+    /// Sets the body of the response to `value` serialized as JSON, with a matching
+    /// `Content-Type` header.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(ResponseBuilder)`: The builder with the JSON body.
+    /// - `Err(serde_json::Error)`: If `value` cannot be serialized, e.g. a map with non-string keys.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<ResponseBuilder, serde_json::Error> {
+        let body = serde_json::to_vec(value)?;
+
+        Ok(self
+            .header(HttpHeader::ContentType("application/json".to_string()))
+            .body(body))
+    }
+
+    /// Sets the trailers sent after the body.
+    ///
+    /// Trailers can only follow a chunked body, so they are ignored unless the body is a stream
+    /// of unknown length.
+    pub fn trailers(mut self, trailers: Trailers) -> ResponseBuilder {
+        self.trailers = Some(trailers);
+
+        self
+    }
+
+    /// Points the response to `location` with a `Location` header.
+    ///
+    /// The status code is kept as given to `Response::builder`, and should be a redirect code
+    /// such as `HttpCode::Found`.
+    pub fn redirect(self, location: &str) -> ResponseBuilder {
+        self.header(HttpHeader::Location(location.to_string()))
+    }
+
+    /// Creates the response.
+    ///
+    /// A `Content-Length` header is added for every body of known length, including empty ones,
+    /// and `Transfer-Encoding: chunked` for streams of unknown length, along with a `Trailer`
+    /// header if there are trailers. Neither is added on `204 No Content` and
+    /// `304 Not Modified` responses, which never have a body.
+    pub fn build(self) -> Response {
+        let mut headers = self.headers;
+        let bodiless = matches!(self.status_code, HttpCode::NoContent | HttpCode::NotModified);
+        let chunked = !bodiless && self.body.len().is_none();
+        if !bodiless {
+            headers.push(match self.body.len() {
+                Some(len) => HttpHeader::ContentLength(len),
+                None => HttpHeader::TransferEncoding("chunked".to_string()),
+            });
+        }
+
+        let trailers = self.trailers.filter(|_| chunked);
+        if let Some(trailers) = &trailers {
+            headers.push(HttpHeader::Trailer(trailers.names().join(", ")));
+        }
+
+        Response {
+            status_code: self.status_code,
+            status_text: self.status_code.to_string(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: (!headers.is_empty()).then_some(headers),
+            body: self.body,
+            trailers,
+        }
+    }
+}
+
+impl IntoResponse for ResponseBuilder {
+    fn into_response(self) -> Response {
+        self.build()
     }
 }
 
@@ -54,7 +364,8 @@ mod tests {
                 HttpHeader::ContentEncoding("deflate".to_string()),
                 HttpHeader::ContentLength(27),
             ]),
-            body: Some("{\"message\":\"Success\"}".to_string()),
+            body: Body::from("{\"message\":\"Success\"}"),
+            trailers: None,
         };
 
         let expected = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: deflate\r\nContent-Length: 27\r\n\r\n{\"message\":\"Success\"}";
@@ -70,7 +381,8 @@ mod tests {
             status_text: "Not Found".to_string(),
             http_version: "HTTP/1.0".to_string(),
             headers: None,
-            body: Some("The requested resource was not found.".to_string()),
+            body: Body::from("The requested resource was not found."),
+            trailers: None,
         };
 
         let expected = "HTTP/1.0 404 Not Found\r\n\r\nThe requested resource was not found.";
@@ -79,6 +391,21 @@ mod tests {
         assert_eq!(formatted, expected);
     }
 
+    #[test]
+    fn test_display_response_with_binary_body() {
+        let response = Response {
+            status_code: HttpCode::Ok,
+            status_text: "OK".to_string(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: None,
+            body: Body::from(b"caf\xc3\xa9 \xff".to_vec()),
+            trailers: None,
+        };
+
+        assert_eq!(response.to_string(), "HTTP/1.1 200 OK\r\n\r\ncafé \u{FFFD}");
+        assert_eq!(response.head(), "HTTP/1.1 200 OK\r\n\r\n");
+    }
+
     #[test]
     fn test_display_response_without_body() {
         let response = Response {
@@ -89,7 +416,8 @@ mod tests {
                 HttpHeader::ContentType("text/plain".to_string()),
                 HttpHeader::ContentEncoding("gzip".to_string()),
             ]),
-            body: None,
+            body: Body::Empty,
+            trailers: None,
         };
 
         let expected = "HTTP/1.1 204 No Content\r\nContent-Type: text/plain\r\nContent-Encoding: gzip\r\n\r\n";
@@ -97,4 +425,205 @@ mod tests {
 
         assert_eq!(formatted, expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_builder_computes_content_length() {
+        let response = Response::created()
+            .header(HttpHeader::ContentType("text/plain".to_string()))
+            .header(HttpHeader::ContentLength(999))
+            .body("done")
+            .build();
+
+        assert_eq!(
+            response.to_string(),
+            "HTTP/1.1 201 Created\r\nContent-Type: text/plain\r\nContent-Length: 4\r\n\r\ndone"
+        );
+        assert_eq!(
+            Response::not_found().build().to_string(),
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+        );
+        assert_eq!(
+            Response::builder(HttpCode::NoContent).build().to_string(),
+            "HTTP/1.1 204 No Content\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_builder_streamed_body_is_chunked() {
+        let response = Response::ok()
+            .body(Body::Stream(Box::new(&b"streamed"[..])))
+            .build();
+        assert!(response.is_chunked());
+
+        let mut output = Vec::new();
+        response.write_to(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n8\r\nstreamed\r\n0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_builder_sized_stream_has_content_length() {
+        let response = Response::ok()
+            .body(Body::SizedStream(Box::new(&b"sized"[..]), 5))
+            .build();
+        assert!(!response.is_chunked());
+
+        let mut output = Vec::new();
+        response.write_to(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nsized"
+        );
+    }
+
+    #[test]
+    fn test_builder_json_and_redirect() {
+        let response = Response::ok().json(&vec![1, 2]).unwrap().build();
+        assert_eq!(
+            response.to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 5\r\n\r\n[1,2]"
+        );
+
+        let map = std::collections::HashMap::from([((1, 2), 3)]);
+        assert!(Response::ok().json(&map).is_err());
+
+        let response = Response::builder(HttpCode::MovedPermanently)
+            .redirect("https://example.com/")
+            .into_response();
+        assert_eq!(
+            response.head(),
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: https://example.com/\r\nContent-Length: 0\r\n\r\n"
+        );
+    }
+
+    fn request(version: &str, headers: &[&str]) -> Request {
+        let mut lines = Vec::from([format!("GET / {}", version)]);
+        lines.extend(headers.iter().map(|h| h.to_string()));
+
+        Request {
+            request: lines,
+            http_version: version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn streamed_with_trailers() -> Response {
+        Response::ok()
+            .body(Body::Stream(Box::new(&b"report"[..])))
+            .trailers(Trailers::new(&["X-Checksum"], || {
+                Vec::from([HttpHeader::Custom("X-Checksum".to_string(), "42".to_string())])
+            }))
+            .build()
+    }
+
+    fn written(response: Response) -> String {
+        let mut output = Vec::new();
+        response.write_to(&mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_trailers_sent_when_accepted() {
+        let mut response = streamed_with_trailers();
+        response.prepare_for(&request("HTTP/1.1", &["TE: trailers, deflate;q=0.5"]));
+
+        assert_eq!(
+            written(response),
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: X-Checksum\r\n\r\n6\r\nreport\r\n0\r\nX-Checksum: 42\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_trailers_dropped_when_not_accepted() {
+        let mut response = streamed_with_trailers();
+        response.prepare_for(&request("HTTP/1.1", &[]));
+
+        assert!(response.trailers.is_none());
+        assert_eq!(
+            written(response),
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nreport\r\n0\r\n\r\n"
+        );
+
+        let response = Response::ok()
+            .body("sized")
+            .trailers(Trailers::new(&["X-Checksum"], Vec::new))
+            .build();
+        assert!(response.trailers.is_none());
+        assert!(!response.head().contains("Trailer"));
+    }
+
+    #[test]
+    fn test_redirect_resolved_against_request() {
+        let mut response = Response::redirect("../drafts/7", RedirectKind::SeeOther).build();
+        response.prepare_for(&Request {
+            url: Some(crate::url::parser::Url::new("/posts/42/publish").unwrap()),
+            http_version: "HTTP/1.1".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            response.head(),
+            "HTTP/1.1 303 See Other\r\nLocation: /posts/drafts/7\r\nContent-Length: 0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_to_sends_head_and_body_in_one_write() {
+        /// Counts the writes it receives, accepting everything at once.
+        #[derive(Default)]
+        struct Socket {
+            data: Vec<u8>,
+            writes: usize,
+        }
+
+        impl Write for Socket {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.write_vectored(&[io::IoSlice::new(buf)])
+            }
+
+            fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+                self.writes += 1;
+                bufs.iter().for_each(|buf| self.data.extend_from_slice(buf));
+
+                Ok(bufs.iter().map(|buf| buf.len()).sum())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        for body in ["first", "second response"] {
+            let mut socket = Socket::default();
+            Response::ok().body(body).build().write_to(&mut socket).unwrap();
+
+            assert_eq!(socket.writes, 1);
+            assert_eq!(
+                String::from_utf8(socket.data).unwrap(),
+                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+            );
+        }
+    }
+
+    #[test]
+    fn test_http_1_0_falls_back_to_close_delimited_body() {
+        let mut response = streamed_with_trailers();
+        response.prepare_for(&request("HTTP/1.0", &["TE: trailers"]));
+
+        assert!(!response.is_chunked());
+        assert_eq!(written(response), "HTTP/1.0 200 OK\r\nConnection: close\r\n\r\nreport");
+    }
+
+    #[test]
+    fn test_unsupported_version_is_not_echoed() {
+        for version in ["HTTP/2.0", "FOO\r\nSet-Cookie: a=b", ""] {
+            let mut response = Response::ok().body("hi").build();
+            response.prepare_for(&request(version, &[]));
+
+            assert_eq!(written(response), "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi");
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! # Error Module
+//!
+//! This module defines `ServerError`, an error that knows which response it should become, and
+//! the `ErrorRenderer` trait, which turns it into one.
+//!
+//! A `ServerError` carries an `HttpCode`, a public message that is safe to show to the client, and
+//! an optional private cause that is only logged. Handlers can return it directly, or return any
+//! other error and let the router convert it:
+//!
+//! - a `ServerError` or an extractor `Rejection` keeps its status and message,
+//! - an `io::Error` becomes a `404 Not Found` (`NotFound`), a `403 Forbidden` (`PermissionDenied`)
+//!   or a `500 Internal Server Error`,
+//! - any other error becomes a `500 Internal Server Error`, with the error as its cause.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use std::fs;
+//!
+//! use server::{
+//!     http::code::HttpCode,
+//!     request::Request,
+//!     response::{IntoResponse, Response, ServerError},
+//!     router::{extract::Path, Router},
+//! };
+//!
+//! fn read(Path(name): Path<String>) -> Result<Vec<u8>, ServerError> {
+//!     if name.starts_with('.') {
+//!         return Err(ServerError::new(HttpCode::Forbidden, "Hidden files are not served"));
+//!     }
+//!
+//!     // A missing file becomes a `404 Not Found`.
+//!     Ok(fs::read(name)?)
+//! }
+//!
+//! let router = Router::new()
+//!     .get("/read/:name", read)
+//!     .error_renderer(|_: &Request, error: ServerError| -> Response {
+//!         (error.code, format!("Sorry! {}", error.message)).into_response()
+//!     });
+//! ```
+
+use std::{error::Error, fmt::Display, io};
+
+use crate::{http::code::HttpCode, request::Request, router::extract::Rejection};
+
+use super::{builder::Response, into_response::IntoResponse};
+
+/// An error to be reported to the client with a specific status code.
+#[derive(Debug)]
+pub struct ServerError {
+    /// The status code of the response.
+    pub code: HttpCode,
+
+    /// A message for the client. It must not reveal anything about the server's internals.
+    pub message: String,
+
+    cause: Option<anyhow::Error>,
+}
+
+impl ServerError {
+    /// Creates an error with `code` and a public `message`.
+    pub fn new(code: HttpCode, message: impl Into<String>) -> ServerError {
+        ServerError {
+            code,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// Creates a `400 Bad Request` error.
+    pub fn bad_request(message: impl Into<String>) -> ServerError {
+        ServerError::new(HttpCode::BadRequest, message)
+    }
+
+    /// Creates a `404 Not Found` error.
+    pub fn not_found(message: impl Into<String>) -> ServerError {
+        ServerError::new(HttpCode::NotFound, message)
+    }
+
+    /// Creates a `500 Internal Server Error` caused by `cause`, with a generic public message.
+    pub fn internal(cause: impl Into<anyhow::Error>) -> ServerError {
+        let code = HttpCode::InternalServerError;
+
+        ServerError::new(code, code.to_string()).with_cause(cause)
+    }
+
+    /// Attaches the private cause of the error, to be logged but never sent to the client.
+    pub fn with_cause(mut self, cause: impl Into<anyhow::Error>) -> ServerError {
+        self.cause = Some(cause.into());
+
+        self
+    }
+
+    /// Returns the private cause of the error, if any.
+    pub fn cause(&self) -> Option<&anyhow::Error> {
+        self.cause.as_ref()
+    }
+
+    /// Returns `true` for `5xx` errors, which are the server's fault rather than the client's.
+    pub fn is_server_error(&self) -> bool {
+        self.code.to_u16() >= 500
+    }
+}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ServerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause.as_ref().map(|cause| &**cause as &(dyn Error + 'static))
+    }
+}
+
+impl From<io::Error> for ServerError {
+    fn from(error: io::Error) -> Self {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => HttpCode::NotFound,
+            io::ErrorKind::PermissionDenied => HttpCode::Forbidden,
+            _ => HttpCode::InternalServerError,
+        };
+
+        ServerError::new(code, code.to_string()).with_cause(error)
+    }
+}
+
+impl From<Rejection> for ServerError {
+    fn from(rejection: Rejection) -> Self {
+        ServerError::new(rejection.status, rejection.message)
+    }
+}
+
+impl From<anyhow::Error> for ServerError {
+    /// Recovers the `ServerError`, `Rejection` or `io::Error` inside `error`, if there is one.
+    ///
+    /// Context added to `error` is kept as part of the private cause.
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<ServerError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+
+        if let Some(rejection) = error.downcast_ref::<Rejection>() {
+            return ServerError::from(rejection.clone());
+        }
+
+        match error.chain().find_map(|e| e.downcast_ref::<io::Error>()) {
+            Some(io_error) => {
+                let code = ServerError::from(io::Error::from(io_error.kind())).code;
+
+                ServerError::new(code, code.to_string()).with_cause(error)
+            }
+            None => ServerError::internal(error),
+        }
+    }
+}
+
+impl IntoResponse for ServerError {
+    /// Renders the error as a plain text response containing its public message.
+    fn into_response(self) -> Response {
+        (self.code, self.message).into_response()
+    }
+
+    fn into_result(self) -> Result<Response, anyhow::Error> {
+        Err(self.into())
+    }
+}
+
+/// Turns the errors returned by handlers into responses.
+///
+/// Closures taking `(&Request, ServerError)` and returning anything that implements
+/// `IntoResponse` are renderers. The default renderer, `error_page::render_error`, responds with
+/// the error's status code and its public message, formatted according to the `Accept` header.
+pub trait ErrorRenderer: Send + Sync + 'static {
+    /// Renders `error`, which was returned while handling `request`.
+    fn render(&self, request: &Request, error: ServerError) -> Response;
+}
+
+impl<F, R> ErrorRenderer for F
+where
+    F: Fn(&Request, ServerError) -> R + Send + Sync + 'static,
+    R: IntoResponse,
+{
+    fn render(&self, request: &Request, error: ServerError) -> Response {
+        self(request, error).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_io_errors() {
+        let error = ServerError::from(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(error.code, HttpCode::NotFound);
+        assert_eq!(error.message, "Not Found");
+        assert!(error.cause().is_some());
+
+        let error = ServerError::from(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(error.code, HttpCode::Forbidden);
+
+        let error = ServerError::from(io::Error::other("disk on fire"));
+        assert_eq!(error.code, HttpCode::InternalServerError);
+        assert_eq!(error.message, "Internal Server Error");
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        let error = ServerError::from(anyhow::Error::new(ServerError::bad_request("no")));
+        assert_eq!((error.code, error.message.as_str()), (HttpCode::BadRequest, "no"));
+
+        let error = ServerError::from(anyhow::Error::new(Rejection::bad_request("bad id")));
+        assert_eq!((error.code, error.message.as_str()), (HttpCode::BadRequest, "bad id"));
+
+        let error = ServerError::from(
+            Err::<(), _>(io::Error::from(io::ErrorKind::NotFound))
+                .context("Failed to open /secret/file")
+                .unwrap_err(),
+        );
+        assert_eq!(error.code, HttpCode::NotFound);
+        assert_eq!(error.message, "Not Found");
+        assert_eq!(error.cause().unwrap().to_string(), "Failed to open /secret/file");
+
+        let error = ServerError::from(anyhow::anyhow!("database password is hunter2"));
+        assert_eq!(error.code, HttpCode::InternalServerError);
+        assert!(!error.message.contains("hunter2"));
+        assert!(error.source().unwrap().to_string().contains("hunter2"));
+    }
+
+    #[test]
+    fn test_render() {
+        let response = ServerError::internal(anyhow::anyhow!("secret")).into_response();
+        assert_eq!(
+            response.to_string(),
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 21\r\n\r\nInternal Server Error"
+        );
+
+        let renderer = |_: &Request, error: ServerError| error.code;
+        let response = renderer.render(&Request::default(), ServerError::not_found("gone"));
+        assert_eq!(response.to_string(), "HTTP/1.1 404 Not Found\r\n\r\n");
+
+        assert!(ServerError::not_found("gone").into_result().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! # Error Page Module
+//!
+//! This module renders `ServerError`s in the format the client asked for with its `Accept`
+//! header:
+//!
+//! - `text/plain`: the public message, which is also used when the client expresses no preference,
+//! - `text/html`: a minimal page showing the status and the message, for browsers,
+//! - `application/problem+json` (or `application/json`): an RFC 9457 problem details object,
+//!   for API clients.
+//!
+//! `render_error` is the router's default `ErrorRenderer`. Custom renderers can reuse
+//! `ErrorFormat` to handle only some formats themselves.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     request::Request,
+//!     response::{
+//!         error_page::{render_error, ErrorFormat},
+//!         IntoResponse, Response, ServerError,
+//!     },
+//!     router::Router,
+//! };
+//!
+//! assert_eq!(ErrorFormat::negotiate(Some("text/html,*/*;q=0.8")), ErrorFormat::Html);
+//! assert_eq!(ErrorFormat::negotiate(Some("application/json")), ErrorFormat::Problem);
+//! assert_eq!(ErrorFormat::negotiate(None), ErrorFormat::Text);
+//!
+//! let router = Router::new().error_renderer(|request: &Request, error: ServerError| -> Response {
+//!     match ErrorFormat::negotiate(request.header("Accept")) {
+//!         ErrorFormat::Html => (error.code, format!("<h1>Oops! {}</h1>", error.message))
+//!             .into_response(),
+//!         _ => render_error(request, error),
+//!     }
+//! });
+//! # let _ = router;
+//! ```
+
+use serde_json::json;
+
+use crate::{
+    http::{accept::negotiate, header::HttpHeader},
+    request::Request,
+};
+
+use super::{builder::Response, error::ServerError};
+
+/// A format for error responses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorFormat {
+    /// `text/plain`: the public message only.
+    Text,
+
+    /// `text/html`: a page showing the status and the public message.
+    Html,
+
+    /// `application/problem+json`: an RFC 9457 problem details object.
+    Problem,
+}
+
+/// The media types offered by `ErrorFormat::negotiate`, in order of preference.
+const MEDIA_TYPES: &[(&str, ErrorFormat)] = &[
+    ("text/plain", ErrorFormat::Text),
+    ("text/html", ErrorFormat::Html),
+    ("application/problem+json", ErrorFormat::Problem),
+    ("application/json", ErrorFormat::Problem),
+];
+
+impl ErrorFormat {
+    /// Picks the format preferred by an `Accept` header, falling back to `Text` when none of the
+    /// formats is acceptable.
+    pub fn negotiate(accept: Option<&str>) -> ErrorFormat {
+        let available = MEDIA_TYPES.iter().map(|(m, _)| *m).collect::<Vec<_>>();
+
+        negotiate(accept, &available)
+            .and_then(|chosen| MEDIA_TYPES.iter().find(|(m, _)| *m == chosen))
+            .map_or(ErrorFormat::Text, |(_, format)| *format)
+    }
+
+    /// Renders `error` in this format.
+    pub fn render(self, error: &ServerError) -> Response {
+        let (content_type, body) = match self {
+            ErrorFormat::Text => ("text/plain; charset=utf-8", error.message.clone()),
+            ErrorFormat::Html => ("text/html; charset=utf-8", html_page(error)),
+            ErrorFormat::Problem => ("application/problem+json", problem_details(error)),
+        };
+
+        Response::builder(error.code)
+            .header(HttpHeader::ContentType(content_type.to_string()))
+            .body(body)
+            .build()
+    }
+}
+
+/// Renders `error` in the format negotiated from the request's `Accept` header.
+///
+/// The response carries `Vary: Accept`, since its format depends on that header.
+pub fn render_error(request: &Request, error: ServerError) -> Response {
+    let mut response = ErrorFormat::negotiate(request.header("Accept")).render(&error);
+    response
+        .headers
+        .get_or_insert_with(Vec::new)
+        .push(HttpHeader::Vary("Accept".to_string()));
+
+    response
+}
+
+fn html_page(error: &ServerError) -> String {
+    let title = format!("{} {}", error.code.to_u16(), error.code);
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<p>{}</p>\n</body>\n</html>\n",
+        escape_html(&error.message)
+    )
+}
+
+fn problem_details(error: &ServerError) -> String {
+    json!({
+        "type": "about:blank",
+        "title": error.code.to_string(),
+        "status": error.code.to_u16(),
+        "detail": error.message,
+    })
+    .to_string()
+}
+
+/// Escapes the characters that are special in HTML text and attribute values.
+fn escape_html(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::code::HttpCode;
+
+    fn request(accept: Option<&str>) -> Request {
+        let mut lines = vec!["GET / HTTP/1.1".to_string()];
+        lines.extend(accept.map(|a| format!("Accept: {}", a)));
+
+        Request {
+            request: lines,
+            ..Default::default()
+        }
+    }
+
+    fn text(response: &Response) -> String {
+        String::from_utf8_lossy(response.body.as_bytes().unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(ErrorFormat::negotiate(None), ErrorFormat::Text);
+        assert_eq!(ErrorFormat::negotiate(Some("*/*")), ErrorFormat::Text);
+        assert_eq!(ErrorFormat::negotiate(Some("text/html")), ErrorFormat::Html);
+        assert_eq!(
+            ErrorFormat::negotiate(Some("application/problem+json")),
+            ErrorFormat::Problem
+        );
+        assert_eq!(ErrorFormat::negotiate(Some("image/png")), ErrorFormat::Text);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let response = render_error(&request(None), ServerError::not_found("No such user"));
+
+        assert_eq!(
+            response.to_string(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 12\r\nVary: Accept\r\n\r\nNo such user"
+        );
+    }
+
+    #[test]
+    fn test_render_html_escapes_message() {
+        let error = ServerError::bad_request("<script>alert('x')</script>");
+        let response = render_error(&request(Some("text/html")), error);
+
+        assert_eq!(response.status_code, HttpCode::BadRequest);
+        assert!(response.head().contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(text(&response).contains("<h1>400 Bad Request</h1>"));
+        assert!(text(&response).contains("<p>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;</p>"));
+    }
+
+    #[test]
+    fn test_render_problem_details() {
+        let error = ServerError::internal(anyhow::anyhow!("secret"));
+        let response = render_error(&request(Some("application/json")), error);
+        let body: serde_json::Value = serde_json::from_str(&text(&response)).unwrap();
+
+        assert!(response.head().contains("Content-Type: application/problem+json\r\n"));
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Internal Server Error",
+                "status": 500,
+                "detail": "Internal Server Error",
+            })
+        );
+    }
+}
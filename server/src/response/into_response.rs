@@ -0,0 +1,201 @@
+//! # IntoResponse Module
+//!
+//! This module defines the `IntoResponse` trait, for types that handlers can return.
+//!
+//! Besides `Response` itself, handlers can return:
+//!
+//! - an `HttpCode`, for an empty response with that status,
+//! - a `&'static str` or `String`, for a `200 OK` plain text response,
+//! - a `Vec<u8>`, for a `200 OK` binary response,
+//! - a `(HttpCode, T)` tuple, to change the status of any of the above,
+//! - a `Result<T, E>`, where an error is passed on to the router instead of becoming a response.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     http::code::HttpCode,
+//!     request::Request,
+//!     response::IntoResponse,
+//!     router::{extract::Path, Router},
+//! };
+//!
+//! fn hello(Path(name): Path<String>) -> impl IntoResponse {
+//!     format!("Hello, {}!", name)
+//! }
+//!
+//! fn create(_: &Request, Path(name): Path<String>) -> anyhow::Result<(HttpCode, String)> {
+//!     anyhow::ensure!(!name.is_empty(), "empty name");
+//!
+//!     Ok((HttpCode::Created, name))
+//! }
+//!
+//! let router = Router::new()
+//!     .get("/hello/:name", hello)
+//!     .post("/hello/:name", create);
+//!
+//! let response = "Hello!".into_response();
+//! assert_eq!(response.status_code, HttpCode::Ok);
+//! ```
+
+use crate::http::{code::HttpCode, header::HttpHeader};
+
+use super::{body::Body, builder::Response};
+
+/// Types that can be turned into a response.
+pub trait IntoResponse {
+    /// Converts `self` into a response.
+    fn into_response(self) -> Response;
+
+    /// Converts `self` into a response, or into an error for the router to handle.
+    ///
+    /// Handlers are called through this method. The default always succeeds; `Result` overrides
+    /// it to pass errors on instead of rendering them.
+    fn into_result(self) -> Result<Response, anyhow::Error>
+    where
+        Self: Sized,
+    {
+        Ok(self.into_response())
+    }
+}
+
+/// Creates a `200 OK` response with `body`, described by `content_type` when it is not empty.
+fn ok(content_type: &str, body: Body) -> Response {
+    let headers = body.len().filter(|len| *len > 0).map(|len| {
+        Vec::from([
+            HttpHeader::ContentType(content_type.to_string()),
+            HttpHeader::ContentLength(len),
+        ])
+    });
+
+    Response {
+        status_code: HttpCode::Ok,
+        status_text: HttpCode::Ok.to_string(),
+        http_version: "HTTP/1.1".to_string(),
+        headers,
+        body,
+        trailers: None,
+    }
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for HttpCode {
+    fn into_response(self) -> Response {
+        Response {
+            status_code: self,
+            status_text: self.to_string(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: None,
+            body: Body::Empty,
+            trailers: None,
+        }
+    }
+}
+
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Response {
+        ok("text/plain; charset=utf-8", Body::from(self))
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response {
+        ok("text/plain; charset=utf-8", Body::from(self))
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response {
+        ok("application/octet-stream", Body::from(self))
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (HttpCode, T) {
+    fn into_response(self) -> Response {
+        let (code, inner) = self;
+        let mut response = inner.into_response();
+        response.status_code = code;
+        response.status_text = code.to_string();
+
+        response
+    }
+
+    fn into_result(self) -> Result<Response, anyhow::Error> {
+        let (code, inner) = self;
+
+        inner.into_result().map(|response| (code, response).into_response())
+    }
+}
+
+impl<T, E> IntoResponse for Result<T, E>
+where
+    T: IntoResponse,
+    E: Into<anyhow::Error>,
+{
+    /// Converts `Ok` values as usual and errors into a bodyless `500 Internal Server Error`.
+    fn into_response(self) -> Response {
+        self.into_result()
+            .unwrap_or_else(|_| HttpCode::InternalServerError.into_response())
+    }
+
+    fn into_result(self) -> Result<Response, anyhow::Error> {
+        self.map_err(Into::into)?.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_and_bytes() {
+        let response = "hi".into_response();
+        assert_eq!(
+            response.to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 2\r\n\r\nhi"
+        );
+
+        let response = vec![0u8, 1].into_response();
+        assert_eq!(
+            response.head(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 2\r\n\r\n"
+        );
+        assert_eq!(response.body.as_bytes(), Some(&[0u8, 1][..]));
+
+        assert_eq!(String::new().into_response().to_string(), "HTTP/1.1 200 OK\r\n\r\n");
+    }
+
+    #[test]
+    fn test_status_code() {
+        assert_eq!(
+            HttpCode::NotFound.into_response().to_string(),
+            "HTTP/1.1 404 Not Found\r\n\r\n"
+        );
+
+        let response = (HttpCode::Created, "done").into_response();
+        assert_eq!(response.status_code, HttpCode::Created);
+        assert_eq!(response.status_text, "Created");
+        assert_eq!(response.body.as_bytes(), Some(&b"done"[..]));
+    }
+
+    #[test]
+    fn test_result() {
+        let ok: Result<&str, std::io::Error> = Ok("fine");
+        assert_eq!(ok.into_result().unwrap().status_code, HttpCode::Ok);
+
+        let err: anyhow::Result<String> = Err(anyhow::anyhow!("broken"));
+        assert_eq!(err.into_result().unwrap_err().to_string(), "broken");
+
+        let err: anyhow::Result<String> = Err(anyhow::anyhow!("broken"));
+        assert_eq!(err.into_response().status_code, HttpCode::InternalServerError);
+
+        let nested: anyhow::Result<(HttpCode, anyhow::Result<String>)> =
+            Ok((HttpCode::Accepted, Err(anyhow::anyhow!("inner"))));
+        assert_eq!(nested.into_result().unwrap_err().to_string(), "inner");
+    }
+}
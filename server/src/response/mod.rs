@@ -1,3 +1,14 @@
+pub mod body;
 pub mod builder;
+pub mod error;
+pub mod error_page;
+pub mod into_response;
+pub mod redirect;
+pub mod sse;
+pub mod stream;
 
-pub use builder::Response as Response;
\ No newline at end of file
+pub use body::Body;
+pub use builder::Response as Response;
+pub use builder::ResponseBuilder;
+pub use error::{ErrorRenderer, ServerError};
+pub use into_response::IntoResponse;
@@ -0,0 +1,180 @@
+//! # Redirect Module
+//!
+//! This module defines `RedirectKind`, which picks the status code of a redirect, and
+//! `resolve_location`, which turns the target of a redirect into the URL the client will follow.
+//!
+//! The five redirect statuses differ in two ways: whether clients and caches should remember
+//! the new location, and whether the client may switch to `GET` when following it.
+//!
+//! | Kind                        | Status | Remembered | Method           |
+//! |-----------------------------|--------|------------|------------------|
+//! | `Permanent`                 | 301    | yes        | may become `GET` |
+//! | `PermanentPreservingMethod` | 308    | yes        | kept             |
+//! | `Temporary`                 | 302    | no         | may become `GET` |
+//! | `TemporaryPreservingMethod` | 307    | no         | kept             |
+//! | `SeeOther`                  | 303    | no         | always `GET`     |
+//!
+//! `SeeOther` is the usual answer to a form submission, pointing the browser to a page showing
+//! the result.
+//!
+//! Redirect targets may be relative: `Response::prepare_for` resolves the `Location` header
+//! against the request URL, so `edit` sent in response to `/posts/42/` leads to `/posts/42/edit`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     http::code::HttpCode,
+//!     request::Request,
+//!     response::{redirect::RedirectKind, Response},
+//!     router::{Params, Router},
+//! };
+//!
+//! fn submit(_: &Request, _: Params) -> Response {
+//!     // ...store the submission...
+//!     Response::redirect("thanks", RedirectKind::SeeOther).build()
+//! }
+//!
+//! let router = Router::new()
+//!     .post("/contact/send", submit)
+//!     .redirect("/blog/*path", "/posts/*path", RedirectKind::PermanentPreservingMethod);
+//!
+//! assert_eq!(RedirectKind::SeeOther.code(), HttpCode::SeeOther);
+//! ```
+
+use crate::{http::code::HttpCode, url::parser::Url, url::path::remove_dot_segments};
+
+/// The kind of a redirect; see the module documentation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RedirectKind {
+    /// `301 Moved Permanently`.
+    Permanent,
+
+    /// `308 Permanent Redirect`.
+    PermanentPreservingMethod,
+
+    /// `302 Found`.
+    Temporary,
+
+    /// `307 Temporary Redirect`.
+    TemporaryPreservingMethod,
+
+    /// `303 See Other`.
+    SeeOther,
+}
+
+impl RedirectKind {
+    /// Returns the status code of the redirect.
+    pub fn code(self) -> HttpCode {
+        match self {
+            RedirectKind::Permanent => HttpCode::MovedPermanently,
+            RedirectKind::PermanentPreservingMethod => HttpCode::PermanentRedirect,
+            RedirectKind::Temporary => HttpCode::Found,
+            RedirectKind::TemporaryPreservingMethod => HttpCode::TemporaryRedirect,
+            RedirectKind::SeeOther => HttpCode::SeeOther,
+        }
+    }
+}
+
+/// Resolves the redirect `target` against the URL of the request, as a browser would
+/// (RFC 3986, Section 5.2).
+///
+/// - Absolute URLs (`https://example.com/`) and network-path references (`//example.com/`) are
+///   returned unchanged.
+/// - Absolute paths (`/login`) have their dot segments removed.
+/// - Relative paths (`edit`, `../list`) are resolved against the directory of the request path.
+/// - Query-only (`?page=2`) and fragment-only (`#top`) targets keep the request path.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::{response::redirect::resolve_location, url::parser::Url};
+///
+/// let base = Url::new("/posts/42/comments?page=3").unwrap();
+///
+/// assert_eq!(resolve_location(&base, "https://example.com/"), "https://example.com/");
+/// assert_eq!(resolve_location(&base, "/login?next=%2F"), "/login?next=%2F");
+/// assert_eq!(resolve_location(&base, "edit"), "/posts/42/edit");
+/// assert_eq!(resolve_location(&base, "../../list#top"), "/list#top");
+/// assert_eq!(resolve_location(&base, "?page=4"), "/posts/42/comments?page=4");
+/// assert_eq!(resolve_location(&base, "#top"), "/posts/42/comments?page=3#top");
+/// ```
+pub fn resolve_location(base: &Url, target: &str) -> String {
+    if target.starts_with("//") || has_scheme(target) {
+        return target.to_string();
+    }
+
+    let split = target.find(['?', '#']).unwrap_or(target.len());
+    let (path, rest) = target.split_at(split);
+
+    if path.is_empty() {
+        let query = match &base.query {
+            Some(query) if rest.starts_with('#') => format!("?{}", query),
+            _ => String::new(),
+        };
+
+        return format!("{}{}{}", base.path, query, rest);
+    }
+
+    let path = if path.starts_with('/') {
+        remove_dot_segments(path)
+    } else {
+        let directory = base.path.rfind('/').map_or("/", |i| &base.path[..=i]);
+        let directory = if directory.starts_with('/') { directory } else { "/" };
+
+        remove_dot_segments(&format!("{}{}", directory, path))
+    };
+
+    format!("{}{}", path, rest)
+}
+
+/// Returns `true` if `target` starts with a URI scheme, such as `https:`.
+fn has_scheme(target: &str) -> bool {
+    match target.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        let codes = [
+            RedirectKind::Permanent,
+            RedirectKind::PermanentPreservingMethod,
+            RedirectKind::Temporary,
+            RedirectKind::TemporaryPreservingMethod,
+            RedirectKind::SeeOther,
+        ]
+        .map(|kind| kind.code().to_u16());
+
+        assert_eq!(codes, [301, 308, 302, 307, 303]);
+    }
+
+    #[test]
+    fn test_resolve_location() {
+        let base = Url::new("/a/b/c").unwrap();
+
+        assert_eq!(resolve_location(&base, "mailto:team@example.com"), "mailto:team@example.com");
+        assert_eq!(resolve_location(&base, "//cdn.example.com/x"), "//cdn.example.com/x");
+        assert_eq!(resolve_location(&base, "d"), "/a/b/d");
+        assert_eq!(resolve_location(&base, "./d/"), "/a/b/d/");
+        assert_eq!(resolve_location(&base, ".."), "/a/");
+        assert_eq!(resolve_location(&base, "../../../../d"), "/d");
+        assert_eq!(resolve_location(&base, "/x/./y/../z"), "/x/z");
+        assert_eq!(resolve_location(&base, "d:e/f"), "d:e/f");
+        assert_eq!(resolve_location(&base, "./d:e"), "/a/b/d:e");
+        assert_eq!(resolve_location(&base, ""), "/a/b/c");
+
+        let base = Url::new("/").unwrap();
+        assert_eq!(resolve_location(&base, "login"), "/login");
+    }
+}
@@ -0,0 +1,320 @@
+//! # SSE Module
+//!
+//! This module implements Server-Sent Events, a response that stays open and pushes events to the
+//! client as they happen. Browsers consume it with `EventSource`.
+//!
+//! A handler creates a channel, hands the sending side to whatever produces the events (usually
+//! a thread it spawns), and returns an `Sse` wrapping the receiving side. The worker then writes
+//! every event sent on the channel to the connection, as a `text/event-stream` body.
+//!
+//! - When no event was sent for a while, a comment line is written as a keep-alive, so that
+//!   proxies do not close the idle connection.
+//! - When every sender is dropped, the stream ends and the response completes.
+//! - When the client disconnects, the next write fails and the worker is released. The receiver
+//!   is dropped with the response, so the producer's next `send` fails and it should stop.
+//!
+//! When `EventSource` reconnects, it sends the id of the last event it received in the
+//! `Last-Event-ID` header, which handlers can read with `Header<LastEventId>` to resume the
+//! stream where it left off.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use std::{sync::mpsc, thread, time::Duration};
+//!
+//! use server::{
+//!     response::sse::{Event, Sse},
+//!     router::{
+//!         extract::{Header, LastEventId},
+//!         Router,
+//!     },
+//! };
+//!
+//! fn ticks(last_event_id: Option<Header<LastEventId>>) -> Sse {
+//!     let start = last_event_id
+//!         .and_then(|Header(LastEventId(id))| id.parse::<u64>().ok())
+//!         .map_or(0, |id| id + 1);
+//!     let (sender, receiver) = mpsc::channel();
+//!
+//!     thread::spawn(move || {
+//!         for tick in start.. {
+//!             let event = Event::new(format!("tick {}", tick)).id(tick.to_string());
+//!             if sender.send(event).is_err() {
+//!                 // The client is gone.
+//!                 break;
+//!             }
+//!             thread::sleep(Duration::from_secs(1));
+//!         }
+//!     });
+//!
+//!     Sse::new(receiver).keep_alive(Duration::from_secs(10))
+//! }
+//!
+//! let router = Router::new().get("/ticks", ticks);
+//! ```
+
+use std::{
+    fmt::Display,
+    io::{self, Read},
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+use crate::http::header::HttpHeader;
+
+use super::{body::Body, builder::Response, into_response::IntoResponse};
+
+/// How long a stream stays silent before a keep-alive comment is sent, by default.
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// One event of an event stream.
+///
+/// Every field is optional. `EventSource` dispatches events with data to the listeners of their
+/// `event` type (`message` by default), and remembers their `id` for reconnections. An event
+/// with only `retry` changes how long the client waits before reconnecting.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Creates an event carrying `data`, which may span several lines.
+    pub fn new(data: impl Into<String>) -> Event {
+        Event {
+            data: Some(data.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the id of the event. Line breaks and NUL characters are removed.
+    pub fn id(mut self, id: impl Into<String>) -> Event {
+        self.id = Some(single_line(id.into()).replace('\0', ""));
+
+        self
+    }
+
+    /// Sets the type of the event. Line breaks are removed.
+    pub fn event(mut self, event: impl Into<String>) -> Event {
+        self.event = Some(single_line(event.into()));
+
+        self
+    }
+
+    /// Sets how long the client waits before reconnecting after the connection is lost.
+    pub fn retry(mut self, retry: Duration) -> Event {
+        self.retry = Some(retry);
+
+        self
+    }
+}
+
+impl Display for Event {
+    /// Formats the event as it is sent on the stream, including the blank line ending it.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", id)?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", event)?;
+        }
+        if let Some(data) = &self.data {
+            for line in data.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+                writeln!(f, "data: {}", line)?;
+            }
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry.as_millis())?;
+        }
+
+        writeln!(f)
+    }
+}
+
+/// A `text/event-stream` response sending the events received from a channel.
+///
+/// See the module documentation.
+#[derive(Debug)]
+pub struct Sse {
+    events: Receiver<Event>,
+    keep_alive: Duration,
+}
+
+impl Sse {
+    /// Creates a response streaming `events`, with keep-alives every `DEFAULT_KEEP_ALIVE`.
+    pub fn new(events: Receiver<Event>) -> Sse {
+        Sse {
+            events,
+            keep_alive: DEFAULT_KEEP_ALIVE,
+        }
+    }
+
+    /// Sets how long the stream may stay silent before a keep-alive comment is sent. This also
+    /// bounds how long a disconnected client can go unnoticed.
+    pub fn keep_alive(mut self, interval: Duration) -> Sse {
+        self.keep_alive = interval;
+
+        self
+    }
+}
+
+impl IntoResponse for Sse {
+    fn into_response(self) -> Response {
+        let stream = EventStream {
+            events: self.events,
+            keep_alive: self.keep_alive,
+            pending: Vec::new(),
+            position: 0,
+        };
+
+        Response::ok()
+            .header(HttpHeader::ContentType("text/event-stream".to_string()))
+            .header(HttpHeader::Custom("Cache-Control".to_string(), "no-cache".to_string()))
+            .body(Body::Stream(Box::new(stream)))
+            .build()
+    }
+}
+
+/// The body of an `Sse` response: reading it blocks until the next event or keep-alive.
+struct EventStream {
+    events: Receiver<Event>,
+    keep_alive: Duration,
+    pending: Vec<u8>,
+    position: usize,
+}
+
+impl Read for EventStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.pending.len() {
+            self.pending = match self.events.recv_timeout(self.keep_alive) {
+                Ok(event) => event.to_string().into_bytes(),
+                Err(RecvTimeoutError::Timeout) => b":\n\n".to_vec(),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            };
+            self.position = 0;
+        }
+
+        let read = buf.len().min(self.pending.len() - self.position);
+        buf[..read].copy_from_slice(&self.pending[self.position..self.position + read]);
+        self.position += read;
+
+        Ok(read)
+    }
+}
+
+/// Removes the line breaks from `text`, which would otherwise start a new field.
+fn single_line(text: String) -> String {
+    text.replace(['\r', '\n'], "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{ErrorKind, Write},
+        sync::mpsc,
+        thread,
+    };
+
+    /// A client that hangs up after receiving `capacity` bytes.
+    struct Disconnecting {
+        received: Vec<u8>,
+        capacity: usize,
+    }
+
+    impl Write for Disconnecting {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.received.len() + buf.len() > self.capacity {
+                return Err(io::Error::from(ErrorKind::BrokenPipe));
+            }
+            self.received.extend_from_slice(buf);
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_event_format() {
+        assert_eq!(Event::new("hello").to_string(), "data: hello\n\n");
+        assert_eq!(
+            Event::new("line 1\nline 2\r\nline 3")
+                .id("42")
+                .event("update")
+                .retry(Duration::from_secs(3))
+                .to_string(),
+            "id: 42\nevent: update\ndata: line 1\ndata: line 2\ndata: line 3\nretry: 3000\n\n"
+        );
+        assert_eq!(
+            Event::default().id("1\n\0data: injected").event("a\r\nb").to_string(),
+            "id: 1data: injected\nevent: ab\n\n"
+        );
+        assert_eq!(Event::new("").to_string(), "data: \n\n");
+    }
+
+    #[test]
+    fn test_stream_ends_when_senders_are_dropped() {
+        let (sender, receiver) = mpsc::channel();
+        sender.send(Event::new("first").id("1")).unwrap();
+        sender.send(Event::new("second").id("2")).unwrap();
+        drop(sender);
+
+        let response = Sse::new(receiver).into_response();
+        assert!(response.is_chunked());
+        assert!(response.head().contains("Content-Type: text/event-stream\r\n"));
+        assert!(response.head().contains("Cache-Control: no-cache\r\n"));
+
+        let mut output = Vec::new();
+        response.body.write_to(&mut output).unwrap();
+        assert_eq!(output, b"id: 1\ndata: first\n\nid: 2\ndata: second\n\n");
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let (sender, receiver) = mpsc::channel();
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            sender.send(Event::new("late")).unwrap();
+        });
+
+        let response = Sse::new(receiver)
+            .keep_alive(Duration::from_millis(20))
+            .into_response();
+        let mut output = Vec::new();
+        response.body.write_to(&mut output).unwrap();
+        producer.join().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(":\n\n"));
+        assert!(output.ends_with(":\n\ndata: late\n\n"));
+    }
+
+    #[test]
+    fn test_disconnect_releases_the_stream() {
+        let (sender, receiver) = mpsc::channel();
+        let producer = thread::spawn(move || {
+            let mut sent = 0;
+            while sender.send(Event::new("x".repeat(10))).is_ok() {
+                sent += 1;
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            sent
+        });
+
+        let response = Sse::new(receiver).into_response();
+        let mut client = Disconnecting {
+            received: Vec::new(),
+            capacity: 100,
+        };
+
+        let error = response.body.write_to(&mut client).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BrokenPipe);
+        assert!(producer.join().unwrap() >= 100 / "data: xxxxxxxxxx\n\n".len());
+    }
+}
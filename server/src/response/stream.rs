@@ -0,0 +1,336 @@
+//! # Stream Module
+//!
+//! This module writes streamed response bodies to the connection.
+//!
+//! Bodies are copied from their reader in chunks of at most `CHUNK_SIZE` bytes, and each chunk
+//! is written out completely before the next one is read. A slow client therefore blocks the
+//! copy instead of letting data pile up in memory, so a multi-gigabyte file needs no more than
+//! one chunk of buffer.
+//!
+//! Bodies whose length is not known upfront are sent with the chunked transfer coding
+//! (RFC 9112, Section 7.1) through `ChunkedWriter`. A chunked body can be followed by trailer
+//! fields, computed by `Trailers` once the whole body has been read, e.g. a checksum.
+//!
+//! Pieces of output that belong together, such as a response head and its body or a chunk and
+//! its framing, are sent with `write_all_vectored`, which hands them to the socket in a single
+//! `write_vectored` call instead of one small write each.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use std::io::Write;
+//!
+//! use server::response::stream::{copy_chunks, ChunkedWriter};
+//!
+//! let mut writer = ChunkedWriter::new(Vec::new());
+//! copy_chunks(&mut &b"Hello, world!"[..], &mut writer, None).unwrap();
+//! let output = writer.finish().unwrap();
+//!
+//! assert_eq!(output, b"D\r\nHello, world!\r\n0\r\n\r\n");
+//! ```
+
+use std::{
+    fmt::Debug,
+    io::{self, Cursor, ErrorKind, IoSlice, Read, Write},
+};
+
+use crate::http::header::HttpHeader;
+
+/// The largest number of bytes read from a body at once.
+pub const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Copies `reader` to `writer` in chunks of at most `CHUNK_SIZE` bytes.
+///
+/// With a `limit`, exactly that many bytes are copied, as promised by a `Content-Length` header.
+///
+/// # Returns
+///
+/// - `Ok(usize)`: The number of bytes copied.
+/// - `Err(io::Error)`: If reading or writing fails, or if `reader` ends before `limit` bytes
+///   were copied (`ErrorKind::UnexpectedEof`).
+pub fn copy_chunks(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    limit: Option<usize>,
+) -> io::Result<usize> {
+    let mut buffer = vec![0; CHUNK_SIZE.min(limit.unwrap_or(CHUNK_SIZE))];
+    let mut copied = 0;
+
+    loop {
+        let wanted = limit.map_or(buffer.len(), |limit| buffer.len().min(limit - copied));
+        if wanted == 0 {
+            break;
+        }
+
+        let read = match reader.read(&mut buffer[..wanted]) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        writer.write_all(&buffer[..read])?;
+        copied += read;
+    }
+
+    match limit {
+        Some(limit) if copied < limit => Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("body ended after {} of {} bytes", copied, limit),
+        )),
+        _ => Ok(copied),
+    }
+}
+
+/// Writes all of `buffers` to `writer`, in order, with as few `write_vectored` calls as the
+/// writer accepts. Usually a single call is enough.
+///
+/// # Returns
+///
+/// - `Ok(())`: Every buffer was written completely.
+/// - `Err(io::Error)`: If writing fails, or if the writer stops accepting data
+///   (`ErrorKind::WriteZero`).
+pub fn write_all_vectored<const N: usize>(
+    writer: &mut dyn Write,
+    mut buffers: [&[u8]; N],
+) -> io::Result<()> {
+    while buffers.iter().any(|buffer| !buffer.is_empty()) {
+        let mut written = match writer.write_vectored(&buffers.map(IoSlice::new)) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::WriteZero,
+                    "failed to write whole buffers",
+                ))
+            }
+            Ok(written) => written,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for buffer in &mut buffers {
+            let advance = written.min(buffer.len());
+            *buffer = &buffer[advance..];
+            written -= advance;
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes everything written to it with the chunked transfer coding.
+///
+/// Every non-empty write becomes one chunk. `finish` must be called to write the last chunk,
+/// which tells the client the body is complete.
+#[derive(Debug)]
+pub struct ChunkedWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    /// Creates a writer sending chunks to `inner`.
+    pub fn new(inner: W) -> ChunkedWriter<W> {
+        ChunkedWriter { inner }
+    }
+
+    /// Writes the last chunk and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.finish_with_trailers(&[])
+    }
+
+    /// Writes the last chunk followed by `trailers`, and returns the underlying writer.
+    pub fn finish_with_trailers(mut self, trailers: &[HttpHeader]) -> io::Result<W> {
+        self.inner.write_all(b"0\r\n")?;
+        for trailer in trailers {
+            write!(self.inner, "{}\r\n", trailer)?;
+        }
+        self.inner.write_all(b"\r\n")?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // An empty chunk would end the body early.
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // The size line is formatted on the stack, and sent along with the data it announces.
+        let mut size_line = Cursor::new([0; 20]);
+        write!(size_line, "{:X}\r\n", buf.len())?;
+        let size_line = &size_line.get_ref()[..size_line.position() as usize];
+
+        write_all_vectored(&mut self.inner, [size_line, buf, b"\r\n"])?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Trailer fields sent after a chunked body.
+///
+/// The field names are announced in the `Trailer` header before the body is sent, and the
+/// values are computed once it has been sent completely. Trailers are only sent to clients that
+/// accept them with `TE: trailers`; see `Response::prepare_for`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+///
+/// use server::{http::header::HttpHeader, response::stream::Trailers};
+///
+/// let lines = Arc::new(Mutex::new(0));
+/// let counted = Arc::clone(&lines);
+/// let trailers = Trailers::new(&["X-Line-Count"], move || {
+///     let count = *counted.lock().unwrap();
+///     Vec::from([HttpHeader::Custom("X-Line-Count".to_string(), count.to_string())])
+/// });
+///
+/// *lines.lock().unwrap() += 3;
+/// assert_eq!(trailers.names(), ["X-Line-Count"]);
+/// assert_eq!(trailers.compute()[0].to_string(), "X-Line-Count: 3");
+/// ```
+pub struct Trailers {
+    names: Vec<String>,
+    compute: Box<dyn FnOnce() -> Vec<HttpHeader> + Send>,
+}
+
+impl Trailers {
+    /// Creates trailers with the fields `names`, whose values are returned by `compute`.
+    pub fn new(
+        names: &[&str],
+        compute: impl FnOnce() -> Vec<HttpHeader> + Send + 'static,
+    ) -> Trailers {
+        Trailers {
+            names: names.iter().map(|name| name.to_string()).collect(),
+            compute: Box::new(compute),
+        }
+    }
+
+    /// Returns the names of the trailer fields.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Computes the trailer fields.
+    pub fn compute(self) -> Vec<HttpHeader> {
+        (self.compute)()
+    }
+}
+
+impl Debug for Trailers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trailers")
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns its data a few bytes at a time, to check that chunks are assembled correctly.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_copy_large_body() {
+        let data = (0..CHUNK_SIZE * 3 + 5).map(|i| i as u8).collect::<Vec<_>>();
+        let mut output = Vec::new();
+
+        assert_eq!(copy_chunks(&mut &data[..], &mut output, None).unwrap(), data.len());
+        assert_eq!(output, data);
+    }
+
+    #[test]
+    fn test_copy_with_limit() {
+        let mut output = Vec::new();
+        assert_eq!(copy_chunks(&mut Trickle(b"abcdefgh"), &mut output, Some(5)).unwrap(), 5);
+        assert_eq!(output, b"abcde");
+
+        let error = copy_chunks(&mut &b"abc"[..], &mut Vec::new(), Some(5)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(error.to_string(), "body ended after 3 of 5 bytes");
+    }
+
+    /// Records every call to `write_vectored`, accepting at most `limit` bytes per call.
+    struct Recorder {
+        calls: Vec<Vec<u8>>,
+        limit: usize,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+            let data = bufs.iter().flat_map(|buf| buf.iter().copied());
+            self.calls.push(data.take(self.limit).collect());
+
+            Ok(self.calls.last().unwrap().len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_all_vectored() {
+        let mut writer = Recorder { calls: Vec::new(), limit: usize::MAX };
+        write_all_vectored(&mut writer, [b"head\r\n\r\n", b"", b"body"]).unwrap();
+        assert_eq!(writer.calls, [b"head\r\n\r\nbody".to_vec()]);
+
+        let mut writer = Recorder { calls: Vec::new(), limit: 3 };
+        write_all_vectored(&mut writer, [b"abcd", b"ef", b"ghi"]).unwrap();
+        assert_eq!(writer.calls, [b"abc".to_vec(), b"def".to_vec(), b"ghi".to_vec()]);
+
+        let mut writer = Recorder { calls: Vec::new(), limit: 0 };
+        let error = write_all_vectored(&mut writer, [b"abc"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_chunked_writer() {
+        let mut writer = ChunkedWriter::new(Vec::new());
+        copy_chunks(&mut Trickle(b"hello world"), &mut writer, None).unwrap();
+        writer.write_all(b"").unwrap();
+
+        assert_eq!(
+            writer.finish().unwrap(),
+            b"3\r\nhel\r\n3\r\nlo \r\n3\r\nwor\r\n2\r\nld\r\n0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_chunked_writer_with_trailers() {
+        let mut writer = ChunkedWriter::new(Vec::new());
+        writer.write_all(b"data").unwrap();
+        let trailers = [
+            HttpHeader::Custom("Digest".to_string(), "sha-256=abc".to_string()),
+            HttpHeader::Custom("X-Rows".to_string(), "1".to_string()),
+        ];
+
+        assert_eq!(
+            writer.finish_with_trailers(&trailers).unwrap(),
+            b"4\r\ndata\r\n0\r\nDigest: sha-256=abc\r\nX-Rows: 1\r\n\r\n"
+        );
+    }
+}
@@ -0,0 +1,970 @@
+//! # Router Module
+//!
+//! This module defines the `Router` struct, which holds an application's route table and
+//! dispatches requests to the matching handler.
+//!
+//! Routes are registered once, at startup, with one method per HTTP verb (`get`, `post`, `put`,
+//! `patch`, `delete`) or with `any` to accept every method. Paths use the `RoutePattern` syntax,
+//! and lookups follow the precedence rules of `RouteTree`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     request::Request,
+//!     router::{Params, RouteReturn, Router},
+//!     response::IntoResponse,
+//! };
+//!
+//! fn hello(_: &Request, params: Params) -> RouteReturn {
+//!     let name = params.get("name").cloned().unwrap_or_default();
+//!
+//!     Ok(format!("Hello, {}!", name).into_response())
+//! }
+//!
+//! let router = Router::new()
+//!     .get("/hello/:name", hello)
+//!     .post("/hello/:name", hello);
+//! ```
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use crate::{
+    http::{code::HttpCode, method::HttpMethod},
+    request::Request,
+    response::{
+        error_page::render_error, redirect::RedirectKind, ErrorRenderer, Response, ServerError,
+    },
+    url::pattern::{RoutePattern, Segment},
+};
+
+use super::{
+    handler::{Erased, Handler},
+    middleware::{Middleware, Next},
+    policy::{check_path, PathCheck, SlashPolicy},
+    redirect::Redirect,
+    state::StateMap,
+    tree::{InsertError, RouteTree},
+};
+
+/// Parameters captured from the request path, keyed by name.
+pub type Params = HashMap<String, String>;
+
+/// What a handler produces: a response, or an error for the router to render.
+pub type RouteReturn = Result<Response, anyhow::Error>;
+
+/// The handlers registered for one path, by method.
+struct Endpoint {
+    pattern: String,
+    handlers: Vec<(Option<HttpMethod>, Box<dyn Handler>)>,
+}
+
+impl Endpoint {
+    /// Returns the handler for `method`, preferring a method-specific one over `any`.
+    fn handler(&self, method: HttpMethod) -> Option<&dyn Handler> {
+        self.handlers
+            .iter()
+            .find(|(m, _)| *m == Some(method))
+            .or_else(|| self.handlers.iter().find(|(m, _)| m.is_none()))
+            .map(|(_, handler)| handler.as_ref())
+    }
+}
+
+/// An application's route table.
+///
+/// Handlers can be functions, closures or any other type implementing `Handler`. See the module
+/// documentation for an example.
+pub struct Router {
+    tree: RouteTree<usize>,
+    endpoints: Vec<Endpoint>,
+    layers: Vec<Box<dyn Middleware>>,
+    mounts: Vec<Mount>,
+    state: StateMap,
+    fallback: Box<dyn Handler>,
+    renderer: Box<dyn ErrorRenderer>,
+    trailing_slash: SlashPolicy,
+    duplicate_slashes: SlashPolicy,
+    ignore_case: bool,
+}
+
+/// A router mounted under a path prefix with `Router::nest`.
+struct Mount {
+    prefix: RoutePattern,
+    router: Router,
+}
+
+impl Mount {
+    /// Matches the prefix against the start of `segments`, returning the captures and the rest.
+    fn strip<'s>(
+        &self,
+        segments: &'s [String],
+        ignore_case: bool,
+    ) -> Option<(Params, &'s [String])> {
+        let prefix = self.prefix.segments();
+        if segments.len() < prefix.len() {
+            return None;
+        }
+
+        let mut params = Params::new();
+        for (pattern, segment) in prefix.iter().zip(segments) {
+            match &pattern.segment {
+                Segment::Static(value)
+                    if value == segment || (ignore_case && value.eq_ignore_ascii_case(segment)) => {}
+                Segment::Param { name, constraint }
+                    if constraint.as_ref().map_or(true, |c| c.is_match(segment)) =>
+                {
+                    params.insert(name.to_string(), segment.to_string());
+                }
+                _ => return None,
+            }
+        }
+
+        Some((params, &segments[prefix.len()..]))
+    }
+}
+
+/// A route selected for a request, with everything needed to run it.
+struct Resolved<'a> {
+    layers: Vec<&'a dyn Middleware>,
+    states: Vec<&'a StateMap>,
+    handler: &'a dyn Handler,
+    params: Params,
+    pattern: &'a str,
+}
+
+impl Router {
+    /// Creates a router without any routes.
+    pub fn new() -> Router {
+        Router {
+            tree: RouteTree::new(),
+            endpoints: Vec::new(),
+            layers: Vec::new(),
+            mounts: Vec::new(),
+            state: StateMap::new(),
+            fallback: Box::new(not_found),
+            renderer: Box::new(render_error),
+            trailing_slash: SlashPolicy::Ignore,
+            duplicate_slashes: SlashPolicy::Ignore,
+            ignore_case: false,
+        }
+    }
+
+    /// Registers shared application state, retrievable in handlers with `Request::state`.
+    ///
+    /// One value is kept per type; registering another value of the same type replaces it.
+    pub fn with_state<T: Send + Sync + 'static>(mut self, state: Arc<T>) -> Router {
+        self.state.insert(state);
+
+        self
+    }
+
+    /// Adds `middleware` around every request handled by this router.
+    ///
+    /// Global middleware also runs for requests that match no route, and always runs before any
+    /// per-route middleware. Layers run in the order they were added, regardless of when the
+    /// routes were registered.
+    pub fn layer(mut self, middleware: impl Middleware) -> Router {
+        self.layers.push(Box::new(middleware));
+
+        self
+    }
+
+    /// Sets the handler for requests that match no route, which responds with a `404 Not Found`
+    /// error by default.
+    ///
+    /// The fallback runs inside the global middleware. Only the fallback of the router handling
+    /// the request is used, so fallbacks set on mounted routers have no effect.
+    pub fn fallback<Args: 'static>(mut self, handler: impl Handler<Args>) -> Router {
+        self.fallback = Box::new(Erased::new(handler));
+
+        self
+    }
+
+    /// Sets how errors returned by handlers and middleware are turned into responses.
+    ///
+    /// Errors are first converted to a `ServerError`; see the `response::error` module. Only the
+    /// renderer of the router handling the request is used, so renderers set on mounted routers
+    /// have no effect.
+    pub fn error_renderer(mut self, renderer: impl ErrorRenderer) -> Router {
+        self.renderer = Box::new(renderer);
+
+        self
+    }
+
+    /// Sets how paths whose trailing slash differs from the matching route are treated; see
+    /// `SlashPolicy`. They match by default. Redirects to the canonical path run inside the global
+    /// middleware, like the fallback.
+    ///
+    /// Only the policy of the router handling the request is used, so policies set on mounted
+    /// routers have no effect.
+    pub fn trailing_slash(mut self, policy: SlashPolicy) -> Router {
+        self.trailing_slash = policy;
+
+        self
+    }
+
+    /// Sets how paths with repeated slashes, like `//echo//abc`, are treated; see `SlashPolicy`.
+    /// They match by default.
+    ///
+    /// Only the policy of the router handling the request is used, so policies set on mounted
+    /// routers have no effect.
+    pub fn duplicate_slashes(mut self, policy: SlashPolicy) -> Router {
+        self.duplicate_slashes = policy;
+
+        self
+    }
+
+    /// Makes static segments match regardless of ASCII case, so `/About` reaches the route
+    /// `/about`. Captured parameters keep the case of the request, and a route whose case
+    /// matches exactly takes precedence.
+    ///
+    /// The setting of the router handling the request applies to mounted routers and prefixes.
+    pub fn case_insensitive(mut self, enabled: bool) -> Router {
+        self.ignore_case = enabled;
+
+        self
+    }
+
+    /// Mounts `router` under `prefix`.
+    ///
+    /// Requests whose path starts with `prefix` are matched against the inner router with the
+    /// prefix stripped, so `/api/v1` and an inner route `/users/:id` handle `/api/v1/users/42`.
+    /// Parameters captured by the prefix (e.g. `/tenants/:tenant`) are passed to the inner
+    /// handlers along with their own. The inner router keeps its own middleware, which runs
+    /// inside this router's, and its own state, which takes precedence over this router's.
+    ///
+    /// Mounted routers are tried before this router's own routes, longest prefix first. If the
+    /// inner router has no route for the request, matching falls back to the next mount, then to
+    /// this router's own routes, and finally to a `404 Not Found`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is not a valid `RoutePattern`, or if it contains catch-all or
+    /// optional segments.
+    pub fn nest(mut self, prefix: &str, router: Router) -> Router {
+        let prefix = RoutePattern::parse(prefix)
+            .unwrap_or_else(|e| panic!("Failed to mount router: {}", e));
+
+        if prefix
+            .segments()
+            .iter()
+            .any(|s| s.optional || matches!(s.segment, Segment::CatchAll(_)))
+        {
+            panic!(
+                "Failed to mount router: prefix {:?} may not contain catch-all or optional segments",
+                prefix.as_str()
+            );
+        }
+
+        // Keep longer prefixes first; among equal lengths, the first mounted wins.
+        let index = self
+            .mounts
+            .iter()
+            .position(|m| m.prefix.segments().len() < prefix.segments().len())
+            .unwrap_or(self.mounts.len());
+        self.mounts.insert(index, Mount { prefix, router });
+
+        self
+    }
+
+    /// Registers `handler` for `GET` requests to `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route cannot be registered; see `Router::route`.
+    pub fn get<Args: 'static>(self, path: &str, handler: impl Handler<Args>) -> Router {
+        self.route(Some(HttpMethod::GET), path, handler)
+    }
+
+    /// Registers `handler` for `POST` requests to `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route cannot be registered; see `Router::route`.
+    pub fn post<Args: 'static>(self, path: &str, handler: impl Handler<Args>) -> Router {
+        self.route(Some(HttpMethod::POST), path, handler)
+    }
+
+    /// Registers `handler` for `PUT` requests to `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route cannot be registered; see `Router::route`.
+    pub fn put<Args: 'static>(self, path: &str, handler: impl Handler<Args>) -> Router {
+        self.route(Some(HttpMethod::PUT), path, handler)
+    }
+
+    /// Registers `handler` for `PATCH` requests to `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route cannot be registered; see `Router::route`.
+    pub fn patch<Args: 'static>(self, path: &str, handler: impl Handler<Args>) -> Router {
+        self.route(Some(HttpMethod::PATCH), path, handler)
+    }
+
+    /// Registers `handler` for `DELETE` requests to `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route cannot be registered; see `Router::route`.
+    pub fn delete<Args: 'static>(self, path: &str, handler: impl Handler<Args>) -> Router {
+        self.route(Some(HttpMethod::DELETE), path, handler)
+    }
+
+    /// Registers `handler` for requests to `path` with any method.
+    ///
+    /// Method-specific handlers registered for the same path take precedence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route cannot be registered; see `Router::route`.
+    pub fn any<Args: 'static>(self, path: &str, handler: impl Handler<Args>) -> Router {
+        self.route(None, path, handler)
+    }
+
+    /// Redirects requests to `from`, with any method, to the `to` pattern with the status code
+    /// of `kind`.
+    ///
+    /// Parameters in `to` are filled in with the values captured by the same names in `from`;
+    /// see the `redirect` module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either pattern is invalid, if `to` uses a parameter that `from` does not
+    /// capture, or if the route cannot be registered; see `Router::route`.
+    pub fn redirect(self, from: &str, to: &str, kind: RedirectKind) -> Router {
+        let source = RoutePattern::parse(from)
+            .unwrap_or_else(|e| panic!("Failed to register redirect: {}", e));
+        let redirect = Redirect::new(to, kind);
+
+        let captured = |name: &str| {
+            source.segments().iter().any(|s| match &s.segment {
+                Segment::Param { name: n, .. } | Segment::CatchAll(n) => n == name,
+                Segment::Static(_) => false,
+            })
+        };
+        if let Some(name) = redirect.param_names().find(|name| !captured(name)) {
+            panic!(
+                "Failed to register redirect: target {:?} uses `{}`, which {:?} does not capture",
+                to, name, from
+            );
+        }
+
+        self.any(from, redirect)
+    }
+
+    /// Registers `handler` for `method` (or any method if `None`) at `path`.
+    ///
+    /// Several methods can be registered for the same path, as long as the path is spelled
+    /// identically each time.
+    ///
+    /// # Panics
+    ///
+    /// Route tables are built at startup, so invalid tables are treated as programming errors.
+    /// This function panics if `path` is not a valid `RoutePattern`, if it conflicts with a
+    /// differently spelled route matching the same paths, or if a handler is already
+    /// registered for the same path and method.
+    pub fn route<Args: 'static>(
+        mut self,
+        method: Option<HttpMethod>,
+        path: &str,
+        handler: impl Handler<Args>,
+    ) -> Router {
+        if let Err(e) = self.try_route(method, path, Box::new(Erased::new(handler))) {
+            panic!("Failed to register route: {}", e);
+        }
+
+        self
+    }
+
+    fn try_route(
+        &mut self,
+        method: Option<HttpMethod>,
+        path: &str,
+        handler: Box<dyn Handler>,
+    ) -> Result<(), InsertError> {
+        let index = match self.endpoints.iter().position(|e| e.pattern == path) {
+            Some(index) => index,
+            None => {
+                self.tree.insert(path, self.endpoints.len())?;
+                self.endpoints.push(Endpoint {
+                    pattern: path.to_string(),
+                    handlers: Vec::new(),
+                });
+
+                self.endpoints.len() - 1
+            }
+        };
+
+        let endpoint = &mut self.endpoints[index];
+        if endpoint.handlers.iter().any(|(m, _)| *m == method) {
+            return Err(InsertError::Conflict {
+                pattern: format!("{} {}", Self::method_name(method), path),
+                existing: endpoint.pattern.clone(),
+            });
+        }
+
+        endpoint.handlers.push((method, handler));
+
+        Ok(())
+    }
+
+    fn method_name(method: Option<HttpMethod>) -> String {
+        method.map_or("ANY".to_string(), |m| format!("{:?}", m))
+    }
+
+    /// Dispatches `request` to the handler of the matching route.
+    ///
+    /// The router's state, and that of any mounted router the route belongs to, is attached to
+    /// `request` before the handler is called. The handler runs inside the middleware of the
+    /// same routers.
+    ///
+    /// # Returns
+    ///
+    /// - The handler's response if a route matches the path and method.
+    /// - The fallback's response if no route matches; see `Router::fallback`.
+    /// - A `501 Not Implemented` response if the request has no recognised method.
+    /// - The error renderer's response if the handler or a middleware returns an error. The
+    ///   private cause of `5xx` errors is logged to stderr.
+    /// - The error renderer's `500 Internal Server Error` if the handler or a middleware panics.
+    ///   The panic is logged to stderr and does not propagate, so the worker thread survives.
+    ///
+    /// Every response is adapted to the client with `Response::prepare_for`.
+    pub fn handle(&self, request: &mut Request) -> Response {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.dispatch(request)))
+            .unwrap_or_else(|payload| {
+                let message = panic_message(payload.as_ref());
+
+                Err(ServerError::internal(anyhow::anyhow!("Handler panicked: {}", message)).into())
+            });
+
+        let mut response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                let error = ServerError::from(error);
+                if let Some(cause) = error.cause().filter(|_| error.is_server_error()) {
+                    eprintln!("Request {} failed: {:#}", request.id, cause);
+                }
+
+                self.renderer.render(request, error)
+            }
+        };
+
+        response.prepare_for(request);
+
+        response
+    }
+
+    fn dispatch(&self, request: &mut Request) -> RouteReturn {
+        request.state = self.state.clone();
+
+        let method = request
+            .method
+            .as_ref()
+            .ok_or_else(|| ServerError::new(HttpCode::NotImplemented, "Unsupported HTTP method"))?;
+
+        let segments = request.url.as_ref().map(|url| url.segments());
+        let resolved = segments
+            .and_then(|segments| self.resolve(&segments, *method, self.ignore_case))
+            .and_then(|resolved| {
+                let url = request.url.as_ref()?;
+                let check =
+                    check_path(&url.path, resolved.pattern, self.trailing_slash, self.duplicate_slashes);
+
+                match check {
+                    PathCheck::Accept => Some(Ok(resolved)),
+                    PathCheck::Redirect(path) => Some(Err(match &url.query {
+                        Some(query) if !query.is_empty() => format!("{}?{}", path, query),
+                        _ => path,
+                    })),
+                    PathCheck::Reject => None,
+                }
+            });
+
+        let layers = self.layers.iter().map(|l| l.as_ref()).collect::<Vec<_>>();
+
+        match resolved {
+            Some(Err(location)) => {
+                // Like the fallback, the redirect goes through the global layers only.
+                let redirect = move |_: &Request, _: Params| {
+                    Response::redirect(&location, RedirectKind::PermanentPreservingMethod).build()
+                };
+
+                Next::new(&layers, &redirect).run(request, Params::new())
+            }
+            Some(Ok(resolved)) => {
+                let mut state = StateMap::new();
+                for s in resolved.states {
+                    state.extend(s);
+                }
+                request.state = state;
+
+                Next::new(&resolved.layers, resolved.handler).run(request, resolved.params)
+            }
+            None => Next::new(&layers, self.fallback.as_ref()).run(request, Params::new()),
+        }
+    }
+
+    /// Finds the route for `segments` and `method`, in mounted routers first.
+    fn resolve(
+        &self,
+        segments: &[String],
+        method: HttpMethod,
+        ignore_case: bool,
+    ) -> Option<Resolved<'_>> {
+        let resolved = self
+            .mounts
+            .iter()
+            .find_map(|mount| {
+                let (mut params, rest) = mount.strip(segments, ignore_case)?;
+                let mut resolved = mount.router.resolve(rest, method, ignore_case)?;
+
+                params.extend(resolved.params);
+                resolved.params = params;
+
+                Some(resolved)
+            })
+            .or_else(|| {
+                let found = if ignore_case {
+                    self.tree.find_segments_ignoring_case(segments)?
+                } else {
+                    self.tree.find_segments(segments)?
+                };
+                let endpoint = &self.endpoints[*found.value];
+
+                Some(Resolved {
+                    layers: Vec::new(),
+                    states: Vec::new(),
+                    handler: endpoint.handler(method)?,
+                    params: found.params,
+                    pattern: &endpoint.pattern,
+                })
+            });
+
+        resolved.map(|mut resolved| {
+            let mut layers = self.layers.iter().map(|l| l.as_ref()).collect::<Vec<_>>();
+            layers.append(&mut resolved.layers);
+            resolved.layers = layers;
+            resolved.states.insert(0, &self.state);
+
+            resolved
+        })
+    }
+}
+
+/// Returns the message a panic was started with, if it was a string.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
+
+fn not_found(_: &Request, _: Params) -> RouteReturn {
+    Err(ServerError::not_found("No route matches the request").into())
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Router::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::header::HttpHeader,
+        response::{Body, IntoResponse},
+        router::{extract::Path, middleware::HandlerExt},
+        url::Url,
+    };
+
+    fn request(method: Option<HttpMethod>, path: &str) -> Request {
+        Request {
+            method,
+            url: Some(Url::new(path).unwrap()),
+            http_version: "HTTP/1.1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn reply(body: &str, params: &Params) -> RouteReturn {
+        let mut params = params.iter().collect::<Vec<_>>();
+        params.sort();
+
+        Ok(format!("{} {:?}", body, params).into_response())
+    }
+
+    fn get_user(_: &Request, params: Params) -> RouteReturn {
+        reply("get user", &params)
+    }
+
+    fn post_user(_: &Request, params: Params) -> RouteReturn {
+        reply("post user", &params)
+    }
+
+    fn any_user(_: &Request, params: Params) -> RouteReturn {
+        reply("any user", &params)
+    }
+
+    fn get_me(_: &Request, params: Params) -> RouteReturn {
+        reply("get me", &params)
+    }
+
+    const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 28\r\nVary: Accept\r\n\r\nNo route matches the request";
+
+    fn text(response: &Response) -> String {
+        String::from_utf8_lossy(response.body.as_bytes().unwrap()).to_string()
+    }
+
+    fn dispatch(router: &Router, method: HttpMethod, path: &str) -> String {
+        let response = router.handle(&mut request(Some(method), path));
+
+        match response.status_code {
+            HttpCode::Ok => text(&response),
+            _ => response.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_dispatch_by_method_and_path() {
+        let router = Router::new()
+            .get("/user/:id", get_user)
+            .post("/user/:id", post_user)
+            .get("/user/me", get_me);
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1"), "get user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::POST, "/user/1"), "post user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/me"), "get me []");
+    }
+
+    #[test]
+    fn test_any_is_a_fallback_for_other_methods() {
+        let router = Router::new()
+            .get("/user/:id", get_user)
+            .any("/user/:id", any_user);
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1"), "get user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::DELETE, "/user/1"), "any user [(\"id\", \"1\")]");
+    }
+
+    #[test]
+    fn test_not_found() {
+        let router = Router::new().get("/user/:id", get_user);
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/missing"), NOT_FOUND);
+        assert_eq!(dispatch(&router, HttpMethod::PUT, "/user/1"), NOT_FOUND);
+    }
+
+    #[test]
+    fn test_custom_fallback() {
+        let router = Router::new()
+            .get("/user/:id", get_user)
+            .layer(tag)
+            .fallback(|request: &Request, _: Params| {
+                let path = request.url.as_ref().map(|url| url.path.to_string());
+
+                (HttpCode::NotFound, format!("nothing at {}", path.unwrap_or_default()))
+            });
+
+        let response = router.handle(&mut request(Some(HttpMethod::GET), "/missing"));
+        assert_eq!(response.status_code, HttpCode::NotFound);
+        assert_eq!(text(&response), "[nothing at /missing]");
+    }
+
+    #[test]
+    fn test_not_found_is_negotiated() {
+        let router = Router::new().get("/user/:id", get_user);
+        let mut request = request(Some(HttpMethod::GET), "/missing");
+        request.request = Vec::from([
+            "GET /missing HTTP/1.1".to_string(),
+            "Accept: application/problem+json".to_string(),
+        ]);
+
+        let response = router.handle(&mut request);
+        assert_eq!(response.status_code, HttpCode::NotFound);
+        assert!(response.head().contains("Content-Type: application/problem+json\r\n"));
+        assert!(text(&response).contains("\"status\":404"));
+    }
+
+    #[test]
+    fn test_missing_method_is_not_implemented() {
+        let router = Router::new().any("/", any_user);
+
+        let response = router.handle(&mut request(None, "/"));
+        assert_eq!(response.status_code, HttpCode::NotImplemented);
+        assert_eq!(text(&response), "Unsupported HTTP method");
+    }
+
+    fn explode(_: &Request, params: Params) -> RouteReturn {
+        let bytes = params["n"].as_bytes();
+
+        Ok(String::from_utf8_lossy(&bytes[..10]).to_string().into_response())
+    }
+
+    #[test]
+    fn test_panicking_handler_responds_with_internal_server_error() {
+        let router = Router::new().get("/explode/:n", explode).get("/user/me", get_me);
+
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/explode/1"),
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 21\r\nVary: Accept\r\n\r\nInternal Server Error"
+        );
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/me"), "get me []");
+    }
+
+    #[test]
+    fn test_panic_message() {
+        assert_eq!(panic_message(&"static"), "static");
+        assert_eq!(panic_message(&"owned".to_string()), "owned");
+        assert_eq!(panic_message(&42), "Box<dyn Any>");
+    }
+
+    fn tag(request: &Request, params: Params, next: Next) -> RouteReturn {
+        let mut response = next.run(request, params)?;
+        response.body = Body::from(format!("[{}]", text(&response)));
+
+        Ok(response)
+    }
+
+    #[test]
+    fn test_global_middleware_wraps_every_request() {
+        let router = Router::new()
+            .get("/user/me", get_me)
+            .layer(tag)
+            .get("/user/:id", get_user.with(tag));
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/me"), "[get me []]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1"), "[[get user [(\"id\", \"1\")]]]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/missing"), NOT_FOUND);
+    }
+
+    struct Greeting(&'static str);
+
+    fn greet(request: &Request, params: Params) -> RouteReturn {
+        let greeting = request.state::<Greeting>()?;
+
+        Ok(format!("{}, {}", greeting.0, params["name"]).into_response())
+    }
+
+    #[test]
+    fn test_state_is_available_to_handlers() {
+        let router = Router::new()
+            .with_state(Arc::new(Greeting("Hello")))
+            .get("/greet/:name", greet);
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/greet/world"), "Hello, world");
+    }
+
+    #[test]
+    fn test_missing_state_is_an_error() {
+        let router = Router::new()
+            .get("/greet/:name", greet)
+            .error_renderer(|_: &Request, error: ServerError| {
+                (error.code, error.cause().unwrap().to_string())
+            });
+
+        let response = router.handle(&mut request(Some(HttpMethod::GET), "/greet/world"));
+        assert_eq!(response.status_code, HttpCode::InternalServerError);
+        assert!(text(&response).contains("Greeting` is registered"));
+    }
+
+    fn find_file(_: &Request, params: Params) -> anyhow::Result<String> {
+        if params["name"].starts_with('.') {
+            return Err(ServerError::new(HttpCode::Forbidden, "Hidden").into());
+        }
+
+        Err(std::io::Error::from(std::io::ErrorKind::NotFound).into())
+    }
+
+    #[test]
+    fn test_errors_are_rendered() {
+        let router = Router::new()
+            .get("/files/:name", find_file)
+            .get("/users/:id", |Path(id): Path<u32>| id.to_string());
+
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/files/.env"),
+            "HTTP/1.1 403 Forbidden\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 6\r\nVary: Accept\r\n\r\nHidden"
+        );
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/files/a.txt"),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 9\r\nVary: Accept\r\n\r\nNot Found"
+        );
+
+        let router = router.error_renderer(|_: &Request, error: ServerError| error.code);
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/users/abc"),
+            "HTTP/1.1 400 Bad Request\r\n\r\n"
+        );
+    }
+
+    fn get_tenant(request: &Request, params: Params) -> RouteReturn {
+        let greeting = request.state::<Greeting>()?;
+
+        reply(greeting.0, &params)
+    }
+
+    fn api() -> Router {
+        Router::new()
+            .with_state(Arc::new(Greeting("inner")))
+            .layer(tag)
+            .get("/", get_me)
+            .get("/users/:id", get_tenant)
+    }
+
+    #[test]
+    fn test_nested_router_strips_prefix() {
+        let router = Router::new()
+            .with_state(Arc::new(Greeting("outer")))
+            .nest("/api/v1", api())
+            .get("/users/:id", get_tenant);
+
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/api/v1/users/7"),
+            "[inner [(\"id\", \"7\")]]"
+        );
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/api/v1"), "[get me []]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/users/7"), "outer [(\"id\", \"7\")]");
+    }
+
+    #[test]
+    fn test_nested_router_receives_prefix_params() {
+        let router = Router::new()
+            .layer(tag)
+            .nest("/tenants/:tenant<alpha>", api());
+
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/tenants/acme/users/7"),
+            "[[inner [(\"id\", \"7\"), (\"tenant\", \"acme\")]]]"
+        );
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/tenants/42/users/7"), NOT_FOUND);
+    }
+
+    #[test]
+    fn test_nested_router_falls_back_to_outer_routes() {
+        let router = Router::new()
+            .nest("/user", Router::new().get("/me", get_me))
+            .nest("/user/admin", Router::new().get("/:id", any_user))
+            .get("/user/:id", get_user)
+            .post("/user/me", post_user);
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/me"), "get me []");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/admin/1"), "any user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1"), "get user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::POST, "/user/me"), "post user []");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/me/more"), NOT_FOUND);
+    }
+
+    #[test]
+    fn test_slash_policies() {
+        let routes = || Router::new().get("/user/:id", get_user).get("/docs/", get_me);
+        let redirect = |location: &str| {
+            format!(
+                "HTTP/1.1 308 Permanent Redirect\r\nLocation: {}\r\nContent-Length: 0\r\n\r\n",
+                location
+            )
+        };
+
+        let router = routes();
+        assert_eq!(dispatch(&router, HttpMethod::GET, "//user//1/"), "get user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/docs"), "get me []");
+
+        let router = routes()
+            .trailing_slash(SlashPolicy::Strict)
+            .duplicate_slashes(SlashPolicy::Strict);
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1"), "get user [(\"id\", \"1\")]");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1/"), NOT_FOUND);
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user//1"), NOT_FOUND);
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/docs/"), "get me []");
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/docs"), NOT_FOUND);
+
+        let router = routes()
+            .trailing_slash(SlashPolicy::Redirect)
+            .duplicate_slashes(SlashPolicy::Redirect);
+        assert_eq!(dispatch(&router, HttpMethod::GET, "//user//1/?a=b"), redirect("/user/1?a=b"));
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/docs"), redirect("/docs/"));
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/user/1/nope/"), NOT_FOUND);
+
+        let router = Router::new()
+            .get("/:x", get_me)
+            .trailing_slash(SlashPolicy::Redirect)
+            .duplicate_slashes(SlashPolicy::Ignore);
+        assert_eq!(dispatch(&router, HttpMethod::GET, "//evil.com/"), redirect("/evil.com"));
+    }
+
+    #[test]
+    fn test_slash_redirects_go_through_global_layers() {
+        fn mark(request: &Request, params: Params, next: Next) -> RouteReturn {
+            let mut response = next.run(request, params)?;
+            response
+                .headers
+                .get_or_insert_with(Vec::new)
+                .push(HttpHeader::Custom("X-Layer".to_string(), "seen".to_string()));
+
+            Ok(response)
+        }
+
+        let router = Router::new()
+            .layer(mark)
+            .get("/user/:id", get_user)
+            .trailing_slash(SlashPolicy::Redirect);
+
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/user/1/"),
+            "HTTP/1.1 308 Permanent Redirect\r\nLocation: /user/1\r\nContent-Length: 0\r\nX-Layer: seen\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_static_segments() {
+        let router = Router::new()
+            .nest("/API", Router::new().get("/Users/:id", get_user))
+            .get("/about", get_me);
+
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/About"), NOT_FOUND);
+
+        let router = router.case_insensitive(true);
+        assert_eq!(dispatch(&router, HttpMethod::GET, "/About"), "get me []");
+        assert_eq!(
+            dispatch(&router, HttpMethod::GET, "/api/users/Ada"),
+            "get user [(\"id\", \"Ada\")]"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to mount router: prefix \"/files/*path\" may not contain")]
+    fn test_nest_rejects_catch_all_prefix() {
+        let _ = Router::new().nest("/files/*path", api());
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to register route: Route \"GET /user/:id\" conflicts")]
+    fn test_duplicate_method_panics() {
+        let _ = Router::new()
+            .get("/user/:id", get_user)
+            .get("/user/:id", get_me);
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicts with existing route \"/user/:id\"")]
+    fn test_conflicting_pattern_panics() {
+        let _ = Router::new()
+            .get("/user/:id", get_user)
+            .post("/user/:name", post_user);
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to register route: Invalid route pattern")]
+    fn test_invalid_pattern_panics() {
+        let _ = Router::new().get("/files/*path/x", get_user);
+    }
+}
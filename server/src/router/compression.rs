@@ -0,0 +1,541 @@
+//! # Compression Module
+//!
+//! This module defines the `Compression` middleware, which compresses response bodies with the
+//! content coding preferred by the client's `Accept-Encoding` header: `gzip`, `deflate`, `br`
+//! (Brotli) or `zstd`.
+//!
+//! A response is compressed only if:
+//!
+//! - its `Content-Type` is text-based (`text/*`, JSON, JavaScript, XML or SVG), but is not an
+//!   event stream,
+//! - its body is at least `threshold` bytes long, or is a stream of unknown length,
+//! - it has no `Content-Encoding` yet, and is not a partial (`Content-Range`) response.
+//!
+//! Such responses get a `Vary: Accept-Encoding` header, whether they end up compressed or not. It
+//! is merged into an existing `Vary` header, so nested layers and handlers that vary on other
+//! headers still send a single one.
+//! In-memory bodies are compressed at once and keep a `Content-Length`; streamed bodies are
+//! compressed as they are sent, with the chunked transfer coding.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     request::Request,
+//!     router::{compression::ContentCoding, Compression, HandlerExt, Params, Router},
+//! };
+//!
+//! fn hello(_: &Request, _: Params) -> String {
+//!     "Hello!".repeat(1000)
+//! }
+//!
+//! let router = Router::new()
+//!     .layer(Compression::new())
+//!     .get("/hello", hello)
+//!     .get(
+//!         "/tiny",
+//!         hello.with(
+//!             Compression::new()
+//!                 .threshold(0)
+//!                 .codings(&[ContentCoding::Gzip]),
+//!         ),
+//!     );
+//! ```
+
+use std::{
+    fmt::Display,
+    io::{self, Read},
+    str::FromStr,
+};
+
+use crate::{
+    http::{code::HttpCode, header::HttpHeader},
+    request::Request,
+    response::{Body, Response},
+};
+
+use super::{
+    builder::{Params, RouteReturn},
+    middleware::{Middleware, Next},
+};
+
+/// Bodies smaller than this are not compressed by default: the savings would not be worth it.
+pub const DEFAULT_THRESHOLD: usize = 1024;
+
+/// A content coding that `Compression` can apply.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ContentCoding {
+    /// `gzip`: DEFLATE in the gzip format.
+    Gzip,
+
+    /// `deflate`: DEFLATE in the zlib format.
+    Deflate,
+
+    /// `br`: Brotli.
+    Brotli,
+
+    /// `zstd`: Zstandard.
+    Zstd,
+}
+
+impl ContentCoding {
+    /// Returns the name of the coding, as used in `Accept-Encoding` and `Content-Encoding`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Deflate => "deflate",
+            ContentCoding::Brotli => "br",
+            ContentCoding::Zstd => "zstd",
+        }
+    }
+
+    /// Wraps `reader` in an encoder applying this coding.
+    fn encoder(self, reader: Box<dyn Read + Send>) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            ContentCoding::Gzip => Box::new(flate2::read::GzEncoder::new(
+                reader,
+                flate2::Compression::default(),
+            )),
+            ContentCoding::Deflate => Box::new(flate2::read::ZlibEncoder::new(
+                reader,
+                flate2::Compression::default(),
+            )),
+            ContentCoding::Brotli => Box::new(brotli::CompressorReader::new(reader, 4096, 5, 22)),
+            ContentCoding::Zstd => Box::new(zstd::stream::read::Encoder::new(reader, 3)?),
+        })
+    }
+}
+
+impl Display for ContentCoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ContentCoding {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Ok(ContentCoding::Gzip),
+            "deflate" => Ok(ContentCoding::Deflate),
+            "br" => Ok(ContentCoding::Brotli),
+            "zstd" => Ok(ContentCoding::Zstd),
+            _ => Err("Unsupported content coding"),
+        }
+    }
+}
+
+/// Middleware compressing response bodies; see the module documentation.
+#[derive(Debug, Clone)]
+pub struct Compression {
+    codings: Vec<ContentCoding>,
+    threshold: usize,
+}
+
+impl Compression {
+    /// Creates a compression layer offering every coding, preferring `zstd`, then `br`, `gzip`
+    /// and `deflate`, for bodies of at least `DEFAULT_THRESHOLD` bytes.
+    pub fn new() -> Compression {
+        Compression {
+            codings: Vec::from([
+                ContentCoding::Zstd,
+                ContentCoding::Brotli,
+                ContentCoding::Gzip,
+                ContentCoding::Deflate,
+            ]),
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Sets the smallest body size, in bytes, that gets compressed.
+    pub fn threshold(mut self, threshold: usize) -> Compression {
+        self.threshold = threshold;
+
+        self
+    }
+
+    /// Sets the codings offered, in order of preference. When the client accepts several of them
+    /// equally, the first one is used.
+    pub fn codings(mut self, codings: &[ContentCoding]) -> Compression {
+        self.codings = codings.to_vec();
+
+        self
+    }
+
+    /// Picks the coding the `Accept-Encoding` header prefers among the offered ones.
+    ///
+    /// Codings with a weight of 0 are refused, and `*` stands for any coding not listed.
+    fn negotiate(&self, accept_encoding: &str) -> Option<ContentCoding> {
+        let weights = accept_encoding
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let name = parts.next()?.trim().to_ascii_lowercase();
+                let q = parts
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+                    .map_or(Some(1.0), |(_, value)| value.trim().parse::<f32>().ok())?;
+
+                Some((name, q))
+            })
+            .collect::<Vec<_>>();
+
+        let weight = |coding: ContentCoding| {
+            weights
+                .iter()
+                .find(|(name, _)| name.parse() == Ok(coding))
+                .or_else(|| weights.iter().find(|(name, _)| name == "*"))
+                .map_or(0.0, |(_, q)| *q)
+        };
+
+        self.codings
+            .iter()
+            .map(|coding| (*coding, weight(*coding)))
+            .filter(|(_, q)| *q > 0.0)
+            .fold(None, |best: Option<(ContentCoding, f32)>, (coding, q)| match best {
+                Some((_, best_q)) if best_q >= q => best,
+                _ => Some((coding, q)),
+            })
+            .map(|(coding, _)| coding)
+    }
+
+    /// Returns `true` if `response` should be compressed for clients accepting it.
+    fn is_compressible(&self, response: &Response) -> bool {
+        let headers = response.headers.as_deref().unwrap_or_default();
+
+        let content_type = headers.iter().find_map(|header| match header {
+            HttpHeader::ContentType(value) => Some(value.as_str()),
+            _ => None,
+        });
+        let already_encoded = headers.iter().any(|header| match header {
+            HttpHeader::ContentEncoding(_) => true,
+            HttpHeader::Custom(name, _) => name.eq_ignore_ascii_case("Content-Range"),
+            _ => false,
+        });
+        let large_enough = response.body.len().map_or(true, |len| len >= self.threshold);
+        let has_body = !response.body.is_empty()
+            && !matches!(response.status_code, HttpCode::NoContent | HttpCode::NotModified);
+
+        has_body && large_enough && !already_encoded && content_type.is_some_and(is_compressible_type)
+    }
+
+    /// Replaces the body of `response` with its encoding by `coding`.
+    fn compress(response: &mut Response, coding: ContentCoding) -> io::Result<()> {
+        let body = match std::mem::take(&mut response.body) {
+            Body::Bytes(bytes) => Body::Bytes(read_all(coding, Box::new(io::Cursor::new(bytes)))?),
+            Body::Text(text) => Body::Bytes(read_all(coding, Box::new(io::Cursor::new(text)))?),
+            Body::Stream(reader) | Body::SizedStream(reader, _) => {
+                Body::Stream(coding.encoder(reader)?)
+            }
+            Body::Empty => Body::Empty,
+        };
+
+        let headers = response.headers.get_or_insert_with(Vec::new);
+        headers.retain(|header| {
+            !matches!(header, HttpHeader::ContentLength(_) | HttpHeader::TransferEncoding(_))
+        });
+        headers.push(HttpHeader::ContentEncoding(coding.to_string()));
+        headers.push(match body.len() {
+            Some(len) => HttpHeader::ContentLength(len),
+            None => HttpHeader::TransferEncoding("chunked".to_string()),
+        });
+        response.body = body;
+
+        Ok(())
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::new()
+    }
+}
+
+impl Middleware for Compression {
+    fn call(&self, request: &Request, params: Params, next: Next<'_>) -> RouteReturn {
+        let mut response = next.run(request, params)?;
+        if !self.is_compressible(&response) {
+            return Ok(response);
+        }
+
+        add_vary(response.headers.get_or_insert_with(Vec::new), "Accept-Encoding");
+
+        if let Some(coding) = request
+            .header("Accept-Encoding")
+            .and_then(|accept_encoding| self.negotiate(accept_encoding))
+        {
+            Self::compress(&mut response, coding)?;
+        }
+
+        Ok(response)
+    }
+}
+
+/// Adds `name` to the `Vary` header in `headers`, unless it is already listed or the response
+/// varies on everything (`*`).
+fn add_vary(headers: &mut Vec<HttpHeader>, name: &str) {
+    let existing = headers.iter_mut().find_map(|header| match header {
+        HttpHeader::Vary(value) => Some(value),
+        HttpHeader::Custom(key, value) if key.eq_ignore_ascii_case("Vary") => Some(value),
+        _ => None,
+    });
+
+    match existing {
+        Some(value) => {
+            let listed = value
+                .split(',')
+                .map(str::trim)
+                .any(|field| field == "*" || field.eq_ignore_ascii_case(name));
+            if !listed {
+                *value = if value.trim().is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}, {}", value, name)
+                };
+            }
+        }
+        None => headers.push(HttpHeader::Vary(name.to_string())),
+    }
+}
+
+/// Returns `true` for media types that are worth compressing.
+fn is_compressible_type(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    // Event streams must reach the client as soon as each event is written, which compressors
+    // prevent by buffering.
+    (media_type.starts_with("text/") && media_type != "text/event-stream")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type.as_str(),
+            "application/json" | "application/javascript" | "application/xml" | "image/svg+xml"
+        )
+}
+
+/// Encodes everything `reader` returns with `coding`.
+fn read_all(coding: ContentCoding, reader: Box<dyn Read + Send>) -> io::Result<Vec<u8>> {
+    let mut encoded = Vec::new();
+    coding.encoder(reader)?.read_to_end(&mut encoded)?;
+
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        response::IntoResponse,
+        router::{HandlerExt, Router},
+        url::Url,
+    };
+
+    fn request(accept_encoding: Option<&str>) -> Request {
+        let mut lines = Vec::from(["GET / HTTP/1.1".to_string()]);
+        lines.extend(accept_encoding.map(|a| format!("Accept-Encoding: {}", a)));
+
+        Request {
+            request: lines,
+            method: Some(crate::http::method::HttpMethod::GET),
+            url: Some(Url::new("/").unwrap()),
+            http_version: "HTTP/1.1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn header(response: &Response, name: &str) -> Option<String> {
+        response
+            .headers
+            .iter()
+            .flatten()
+            .map(|header| header.to_string())
+            .find(|header| header.starts_with(&format!("{}: ", name)))
+            .map(|header| header[name.len() + 2..].to_string())
+    }
+
+    fn body(response: Response) -> Vec<u8> {
+        let mut output = Vec::new();
+        response.body.write_to(&mut output).unwrap();
+
+        output
+    }
+
+    fn decode(coding: ContentCoding, data: &[u8]) -> String {
+        let mut decoded = String::new();
+        match coding {
+            ContentCoding::Gzip => flate2::read::GzDecoder::new(data).read_to_string(&mut decoded),
+            ContentCoding::Deflate => flate2::read::ZlibDecoder::new(data).read_to_string(&mut decoded),
+            ContentCoding::Brotli => brotli::Decompressor::new(data, 4096).read_to_string(&mut decoded),
+            ContentCoding::Zstd => zstd::stream::read::Decoder::new(data)
+                .unwrap()
+                .read_to_string(&mut decoded),
+        }
+        .unwrap();
+
+        decoded
+    }
+
+    fn text(_: &Request, _: Params) -> String {
+        "compress me ".repeat(200)
+    }
+
+    fn dispatch(router: &Router, accept_encoding: Option<&str>) -> Response {
+        router.handle(&mut request(accept_encoding))
+    }
+
+    #[test]
+    fn test_vary_is_merged() {
+        fn varies_on_accept(_: &Request, _: Params) -> Response {
+            Response::ok()
+                .header(HttpHeader::ContentType("text/plain".to_string()))
+                .header(HttpHeader::Vary("Accept".to_string()))
+                .body("compress me ".repeat(200))
+                .build()
+        }
+
+        let router = Router::new()
+            .layer(Compression::new())
+            .get("/", varies_on_accept.with(Compression::new().threshold(0)));
+
+        for accept_encoding in [None, Some("gzip")] {
+            let response = dispatch(&router, accept_encoding);
+            let vary = response
+                .headers
+                .iter()
+                .flatten()
+                .filter(|header| header.to_string().starts_with("Vary: "))
+                .count();
+
+            assert_eq!(vary, 1);
+            assert_eq!(header(&response, "Vary"), Some("Accept, Accept-Encoding".to_string()));
+        }
+
+        let mut headers = Vec::from([HttpHeader::Custom("vary".to_string(), "*".to_string())]);
+        add_vary(&mut headers, "Accept-Encoding");
+        assert_eq!(headers[0].to_string(), "vary: *");
+
+        let mut headers = Vec::from([HttpHeader::Vary("accept-encoding".to_string())]);
+        add_vary(&mut headers, "Accept-Encoding");
+        assert_eq!(headers, [HttpHeader::Vary("accept-encoding".to_string())]);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let compression = Compression::new();
+
+        assert_eq!(compression.negotiate("gzip"), Some(ContentCoding::Gzip));
+        assert_eq!(compression.negotiate("gzip, br"), Some(ContentCoding::Brotli));
+        assert_eq!(compression.negotiate("gzip;q=1, br;q=0.5"), Some(ContentCoding::Gzip));
+        assert_eq!(compression.negotiate("*"), Some(ContentCoding::Zstd));
+        assert_eq!(compression.negotiate("*, zstd;q=0"), Some(ContentCoding::Brotli));
+        assert_eq!(compression.negotiate("identity"), None);
+        assert_eq!(compression.negotiate("invalid-encoding"), None);
+    }
+
+    #[test]
+    fn test_compresses_with_every_coding() {
+        for coding in ["gzip", "deflate", "br", "zstd"] {
+            let coding = coding.parse::<ContentCoding>().unwrap();
+            let router = Router::new().layer(Compression::new()).get("/", text);
+            let response = dispatch(&router, Some(coding.as_str()));
+
+            assert_eq!(header(&response, "Content-Encoding"), Some(coding.to_string()));
+            assert_eq!(header(&response, "Vary"), Some("Accept-Encoding".to_string()));
+            let length = header(&response, "Content-Length").unwrap();
+            let data = body(response);
+            assert_eq!(length, data.len().to_string());
+            assert_eq!(decode(coding, &data), "compress me ".repeat(200));
+        }
+    }
+
+    #[test]
+    fn test_compresses_streams_in_chunks() {
+        let stream = |_: &Request, _: Params| {
+            Response::ok()
+                .header(HttpHeader::ContentType("text/csv".to_string()))
+                .body(Body::SizedStream(Box::new(io::Cursor::new("a,b\n".repeat(1000))), 4000))
+                .build()
+        };
+        let router = Router::new().layer(Compression::new()).get("/", stream);
+        let response = dispatch(&router, Some("gzip"));
+
+        assert!(response.is_chunked());
+        assert_eq!(header(&response, "Content-Length"), None);
+        assert_eq!(decode(ContentCoding::Gzip, &body(response)), "a,b\n".repeat(1000));
+    }
+
+    #[test]
+    fn test_skips_ineligible_responses() {
+        let small = |_: &Request, _: Params| "small".into_response();
+        let binary = |_: &Request, _: Params| vec![0u8; 4096].into_response();
+        let encoded = |_: &Request, _: Params| {
+            Response::ok()
+                .header(HttpHeader::ContentType("text/plain".to_string()))
+                .header(HttpHeader::ContentEncoding("gzip".to_string()))
+                .body(vec![0u8; 4096])
+                .build()
+        };
+        let range = |_: &Request, _: Params| {
+            Response::ok()
+                .header(HttpHeader::ContentType("text/plain".to_string()))
+                .header(HttpHeader::Custom(
+                    "Content-Range".to_string(),
+                    "bytes 0-4095/8192".to_string(),
+                ))
+                .body("x".repeat(4096))
+                .build()
+        };
+
+        let events = |_: &Request, _: Params| {
+            Response::ok()
+                .header(HttpHeader::ContentType("text/event-stream".to_string()))
+                .body("data: x\n\n".repeat(512))
+                .build()
+        };
+
+        for (router, length) in [
+            (Router::new().layer(Compression::new()).get("/", small), 5),
+            (Router::new().layer(Compression::new()).get("/", binary), 4096),
+            (Router::new().layer(Compression::new()).get("/", encoded), 4096),
+            (Router::new().layer(Compression::new()).get("/", range), 4096),
+            (Router::new().layer(Compression::new()).get("/", events), 4608),
+        ] {
+            let response = dispatch(&router, Some("gzip"));
+
+            assert_eq!(header(&response, "Vary"), None);
+            assert_eq!(body(response).len(), length);
+        }
+    }
+
+    #[test]
+    fn test_vary_without_accepted_coding() {
+        let router = Router::new().layer(Compression::new()).get("/", text);
+
+        for accept_encoding in [None, Some("identity"), Some("gzip;q=0")] {
+            let response = dispatch(&router, accept_encoding);
+
+            assert_eq!(header(&response, "Content-Encoding"), None);
+            assert_eq!(header(&response, "Vary"), Some("Accept-Encoding".to_string()));
+            assert_eq!(body(response), "compress me ".repeat(200).into_bytes());
+        }
+    }
+
+    #[test]
+    fn test_per_route_configuration() {
+        let router = Router::new().get(
+            "/",
+            (|_: &Request, _: Params| "tiny")
+                .with(Compression::new().threshold(0).codings(&[ContentCoding::Deflate])),
+        );
+        let response = dispatch(&router, Some("gzip, deflate, br"));
+
+        assert_eq!(header(&response, "Content-Encoding"), Some("deflate".to_string()));
+        assert_eq!(decode(ContentCoding::Deflate, &body(response)), "tiny");
+    }
+}
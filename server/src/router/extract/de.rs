@@ -0,0 +1,241 @@
+//! A `serde` deserializer over the parameters captured from a request path.
+//!
+//! Structs and maps are deserialized field by field, by parameter name. Any other type reads the
+//! single captured parameter, so `Path<u32>` works for a route like `/users/:id`. Values are
+//! parsed from their string form with `FromStr`.
+
+use serde::de::{
+    self,
+    value::{Error, MapDeserializer},
+    Error as _, IntoDeserializer, Visitor,
+};
+
+use super::super::builder::Params;
+
+/// Deserializes a `Params` map.
+pub(crate) struct ParamsDeserializer<'a> {
+    params: &'a Params,
+}
+
+impl<'a> ParamsDeserializer<'a> {
+    pub(crate) fn new(params: &'a Params) -> ParamsDeserializer<'a> {
+        ParamsDeserializer { params }
+    }
+
+    fn single(&self) -> Result<ValueDeserializer<'a>, Error> {
+        let mut values = self.params.values();
+
+        match (values.next(), values.next()) {
+            (Some(value), None) => Ok(ValueDeserializer(value)),
+            _ => Err(Error::custom(format!(
+                "expected 1 path parameter, found {}",
+                self.params.len()
+            ))),
+        }
+    }
+}
+
+/// Forwards the deserialization of single values to the only captured parameter.
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ParamsDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(MapDeserializer::new(
+            self.params
+                .iter()
+                .map(|(name, value)| (name.as_str(), ValueDeserializer(value))),
+        ))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_option
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct seq tuple tuple_struct identifier ignored_any
+    }
+}
+
+/// Deserializes one parameter value.
+struct ValueDeserializer<'a>(&'a str);
+
+/// Parses the value with `FromStr` and passes it to the matching visitor method.
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident: $ty:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let value = self.0.parse::<$ty>().map_err(|e| {
+                    Error::custom(format!("cannot parse {:?} as {}: {}", self.0, stringify!($ty), e))
+                })?;
+
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct UserPath {
+        org: String,
+        id: u32,
+        archived: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Users,
+        Teams,
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> Params {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn from_params<T: de::DeserializeOwned>(pairs: &[(&str, &str)]) -> Result<T, Error> {
+        T::deserialize(ParamsDeserializer::new(&params(pairs)))
+    }
+
+    #[test]
+    fn test_deserialize_struct() {
+        let path = from_params::<UserPath>(&[("org", "acme"), ("id", "42")]).unwrap();
+
+        assert_eq!(
+            path,
+            UserPath {
+                org: "acme".to_string(),
+                id: 42,
+                archived: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_single_value() {
+        assert_eq!(from_params::<u32>(&[("id", "42")]).unwrap(), 42);
+        assert_eq!(from_params::<String>(&[("name", "a b")]).unwrap(), "a b");
+        assert_eq!(from_params::<Kind>(&[("kind", "teams")]).unwrap(), Kind::Teams);
+        assert_eq!(from_params::<Option<i8>>(&[("n", "-1")]).unwrap(), Some(-1));
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let error = from_params::<u32>(&[("id", "abc")]).unwrap_err();
+        assert_eq!(error.to_string(), "cannot parse \"abc\" as u32: invalid digit found in string");
+
+        let error = from_params::<u32>(&[("a", "1"), ("b", "2")]).unwrap_err();
+        assert_eq!(error.to_string(), "expected 1 path parameter, found 2");
+
+        let error = from_params::<UserPath>(&[("org", "acme")]).unwrap_err();
+        assert_eq!(error.to_string(), "missing field `id`");
+
+        assert!(from_params::<Kind>(&[("kind", "robots")]).is_err());
+    }
+}
@@ -0,0 +1,516 @@
+//! # Extract Module
+//!
+//! This module defines extractors: types that handlers can take as arguments instead of digging
+//! through the raw `Request` and `Params`.
+//!
+//! - `Path<T>`: the parameters captured from the path, deserialized into `T`. A struct reads
+//!   them by name; any other type reads the single captured parameter.
+//! - `Query<T>`: the query string, deserialized into `T`.
+//! - `Header<T>`: a typed header, such as `Header<UserAgent>`.
+//! - `Json<T>`: a JSON body, deserialized into `T`.
+//! - `Form<T>`: a `application/x-www-form-urlencoded` body, deserialized into `T`.
+//! - `Bytes`: the raw body.
+//!
+//! Wrapping an extractor in `Option` makes it optional: it is `None` when the request lacks what
+//! the extractor reads, such as a header or a body, but a value that is present and invalid is
+//! still rejected. When an extractor fails, the handler is not called and the client receives a
+//! `400 Bad Request` response explaining what was wrong.
+//!
+//! Handlers can take up to six extractors, optionally preceded by the `&Request` itself.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use server::{
+//!     request::Request,
+//!     router::{
+//!         extract::{Header, Path, Query, UserAgent},
+//!         Router,
+//!     },
+//! };
+//!
+//! #[derive(Deserialize)]
+//! struct Pagination {
+//!     page: Option<u32>,
+//! }
+//!
+//! fn list_posts(
+//!     request: &Request,
+//!     Path(user_id): Path<u32>,
+//!     Query(pagination): Query<Pagination>,
+//!     Header(UserAgent(agent)): Header<UserAgent>,
+//! ) -> String {
+//!     format!(
+//!         "posts of user {} (page {}) for {} over {}",
+//!         user_id,
+//!         pagination.page.unwrap_or(1),
+//!         agent,
+//!         request.http_version
+//!     )
+//! }
+//!
+//! let router = Router::new().get("/users/:id/posts", list_posts);
+//! ```
+
+mod de;
+
+use std::{error::Error, fmt::Display};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    http::code::HttpCode,
+    request::Request,
+    response::{IntoResponse, Response},
+};
+
+use super::builder::Params;
+
+use de::ParamsDeserializer;
+
+/// Types that can be created from a request, to be taken as handler arguments.
+pub trait FromRequest: Sized {
+    /// Extracts `Self` from `request`, whose path captured `params`.
+    fn from_request(request: &Request, params: &Params) -> Result<Self, Rejection>;
+}
+
+/// Returned when an extractor fails; rendered as an error response instead of calling the handler.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rejection {
+    /// The status code of the response.
+    pub status: HttpCode,
+
+    /// A message for the client, explaining what was wrong with the request.
+    pub message: String,
+
+    /// Whether the request lacks what the extractor reads, rather than holding an invalid value.
+    /// `Option<T>` turns these rejections into `None`.
+    pub missing: bool,
+}
+
+impl Rejection {
+    /// Creates a `400 Bad Request` rejection.
+    pub fn bad_request(message: impl Into<String>) -> Rejection {
+        Rejection {
+            status: HttpCode::BadRequest,
+            message: message.into(),
+            missing: false,
+        }
+    }
+
+    /// Creates a `400 Bad Request` rejection for a request lacking what the extractor reads.
+    pub fn missing(message: impl Into<String>) -> Rejection {
+        Rejection {
+            missing: true,
+            ..Rejection::bad_request(message)
+        }
+    }
+}
+
+impl IntoResponse for Rejection {
+    /// Renders the rejection as a plain text response.
+    fn into_response(self) -> Response {
+        (self.status, self.message).into_response()
+    }
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for Rejection {}
+
+impl<T: FromRequest> FromRequest for Option<T> {
+    fn from_request(request: &Request, params: &Params) -> Result<Self, Rejection> {
+        match T::from_request(request, params) {
+            Ok(value) => Ok(Some(value)),
+            Err(rejection) if rejection.missing => Ok(None),
+            Err(rejection) => Err(rejection),
+        }
+    }
+}
+
+/// The path parameters, deserialized into `T`. Missing if the path captured no parameters, such
+/// as a skipped optional segment.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Path<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Path<T> {
+    fn from_request(_: &Request, params: &Params) -> Result<Self, Rejection> {
+        T::deserialize(ParamsDeserializer::new(params))
+            .map(Path)
+            .map_err(|e| {
+                let message = format!("Invalid path parameters: {}", e);
+                if params.is_empty() {
+                    Rejection::missing(message)
+                } else {
+                    Rejection::bad_request(message)
+                }
+            })
+    }
+}
+
+/// The query string, deserialized into `T`. A missing query string is treated as empty, and only
+/// counts as missing if `T` cannot be deserialized from it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Query<T> {
+    fn from_request(request: &Request, _: &Params) -> Result<Self, Rejection> {
+        let query = request
+            .url
+            .as_ref()
+            .and_then(|url| url.query.as_ref())
+            .map(|query| query.to_string());
+
+        serde_urlencoded::from_str(query.as_deref().unwrap_or_default())
+            .map(Query)
+            .map_err(|e| {
+                let message = format!("Invalid query string: {}", e);
+                if query.is_none() {
+                    Rejection::missing(message)
+                } else {
+                    Rejection::bad_request(message)
+                }
+            })
+    }
+}
+
+/// A header that can be extracted with `Header<T>`.
+pub trait TypedHeader: Sized {
+    /// The name of the header.
+    const NAME: &'static str;
+
+    /// Parses the header value.
+    fn decode(value: &str) -> Result<Self, String>;
+}
+
+/// A typed request header. Fails if the header is missing or invalid; `Option<Header<T>>` is
+/// `None` only when it is missing.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Header<T>(pub T);
+
+impl<T: TypedHeader> FromRequest for Header<T> {
+    fn from_request(request: &Request, _: &Params) -> Result<Self, Rejection> {
+        let value = request
+            .header(T::NAME)
+            .ok_or_else(|| Rejection::missing(format!("Missing `{}` header", T::NAME)))?;
+
+        T::decode(value)
+            .map(Header)
+            .map_err(|e| Rejection::bad_request(format!("Invalid `{}` header: {}", T::NAME, e)))
+    }
+}
+
+/// The `User-Agent` header.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UserAgent(pub String);
+
+impl TypedHeader for UserAgent {
+    const NAME: &'static str = "User-Agent";
+
+    fn decode(value: &str) -> Result<Self, String> {
+        Ok(UserAgent(value.to_string()))
+    }
+}
+
+/// The `Content-Type` header.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ContentType(pub String);
+
+impl TypedHeader for ContentType {
+    const NAME: &'static str = "Content-Type";
+
+    fn decode(value: &str) -> Result<Self, String> {
+        Ok(ContentType(value.to_string()))
+    }
+}
+
+/// The `Content-Length` header.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ContentLength(pub usize);
+
+impl TypedHeader for ContentLength {
+    const NAME: &'static str = "Content-Length";
+
+    fn decode(value: &str) -> Result<Self, String> {
+        value
+            .parse()
+            .map(ContentLength)
+            .map_err(|e| format!("{}", e))
+    }
+}
+
+/// The `Accept-Encoding` header, as a list of encodings without their weights.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AcceptEncoding(pub Vec<String>);
+
+impl AcceptEncoding {
+    /// Returns `true` if the client accepts `encoding`.
+    pub fn accepts(&self, encoding: &str) -> bool {
+        self.0.iter().any(|e| e.eq_ignore_ascii_case(encoding))
+    }
+}
+
+impl TypedHeader for AcceptEncoding {
+    const NAME: &'static str = "Accept-Encoding";
+
+    fn decode(value: &str) -> Result<Self, String> {
+        let encodings = value
+            .split(',')
+            .filter_map(|e| e.split(';').next())
+            .map(|e| e.trim().to_string())
+            .filter(|e| !e.is_empty())
+            .collect();
+
+        Ok(AcceptEncoding(encodings))
+    }
+}
+
+/// The `Last-Event-ID` header, sent by `EventSource` clients when they reconnect to an event
+/// stream. It holds the id of the last event they received.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LastEventId(pub String);
+
+impl TypedHeader for LastEventId {
+    const NAME: &'static str = "Last-Event-ID";
+
+    fn decode(value: &str) -> Result<Self, String> {
+        Ok(LastEventId(value.trim().to_string()))
+    }
+}
+
+/// Fails unless the request's `Content-Type` is `expected`, ignoring parameters like `charset`.
+///
+/// A request with neither a body nor a `Content-Type` is missing the body.
+fn require_content_type(request: &Request, expected: &str) -> Result<(), Rejection> {
+    if request.body.is_empty() && request.header("Content-Type").is_none() {
+        return Err(Rejection::missing("Missing request body"));
+    }
+
+    let matches = request
+        .header("Content-Type")
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(expected));
+
+    if matches {
+        Ok(())
+    } else {
+        Err(Rejection::bad_request(format!(
+            "Expected `Content-Type: {}`",
+            expected
+        )))
+    }
+}
+
+/// A JSON request body, deserialized into `T`.
+///
+/// Requires `Content-Type: application/json`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    fn from_request(request: &Request, _: &Params) -> Result<Self, Rejection> {
+        require_content_type(request, "application/json")?;
+
+        serde_json::from_slice(&request.body)
+            .map(Json)
+            .map_err(|e| Rejection::bad_request(format!("Invalid JSON body: {}", e)))
+    }
+}
+
+/// A URL-encoded form body, deserialized into `T`.
+///
+/// Requires `Content-Type: application/x-www-form-urlencoded`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Form<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Form<T> {
+    fn from_request(request: &Request, _: &Params) -> Result<Self, Rejection> {
+        require_content_type(request, "application/x-www-form-urlencoded")?;
+
+        serde_urlencoded::from_bytes(&request.body)
+            .map(Form)
+            .map_err(|e| Rejection::bad_request(format!("Invalid form body: {}", e)))
+    }
+}
+
+/// The raw request body.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bytes(pub Vec<u8>);
+
+impl FromRequest for Bytes {
+    fn from_request(request: &Request, _: &Params) -> Result<Self, Rejection> {
+        Ok(Bytes(request.body.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::url::Url;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Search {
+        q: String,
+        page: Option<u32>,
+    }
+
+    fn request(url: &str, headers: &[&str], body: &str) -> Request {
+        let mut request = vec![format!("POST {} HTTP/1.1", url)];
+        request.extend(headers.iter().map(|h| h.to_string()));
+
+        Request {
+            request,
+            url: Some(Url::new(url).unwrap()),
+            http_version: "HTTP/1.1".to_string(),
+            body: body.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn extract<T: FromRequest>(request: &Request) -> Result<T, Rejection> {
+        let params = Params::from([("id".to_string(), "42".to_string())]);
+
+        T::from_request(request, &params)
+    }
+
+    #[test]
+    fn test_path() {
+        let request = request("/", &[], "");
+
+        assert_eq!(extract::<Path<u32>>(&request).unwrap(), Path(42));
+        assert_eq!(
+            extract::<Path<bool>>(&request).unwrap_err().message,
+            "Invalid path parameters: cannot parse \"42\" as bool: provided string was not `true` or `false`"
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let request = self::request("/search?q=rust+lang&page=2", &[], "");
+        let Query(search) = extract::<Query<Search>>(&request).unwrap();
+        assert_eq!(search, Search { q: "rust lang".to_string(), page: Some(2) });
+
+        let request = self::request("/search", &[], "");
+        assert_eq!(
+            extract::<Query<Search>>(&request).unwrap_err().message,
+            "Invalid query string: missing field `q`"
+        );
+    }
+
+    #[test]
+    fn test_header() {
+        let request = request("/", &["user-agent: curl/8.0", "Accept-Encoding: gzip;q=1.0, br"], "");
+
+        assert_eq!(
+            extract::<Header<UserAgent>>(&request).unwrap(),
+            Header(UserAgent("curl/8.0".to_string()))
+        );
+        let Header(encoding) = extract::<Header<AcceptEncoding>>(&request).unwrap();
+        assert!(encoding.accepts("gzip") && encoding.accepts("br"));
+        assert_eq!(
+            extract::<Header<ContentLength>>(&request).unwrap_err().message,
+            "Missing `Content-Length` header"
+        );
+        assert_eq!(extract::<Option<Header<ContentLength>>>(&request).unwrap(), None);
+
+        let request = self::request("/", &["Content-Length: many"], "");
+        let rejection = extract::<Option<Header<ContentLength>>>(&request).unwrap_err();
+        assert_eq!(rejection.status, HttpCode::BadRequest);
+        assert_eq!(
+            rejection.message,
+            "Invalid `Content-Length` header: invalid digit found in string"
+        );
+
+        let request = self::request("/", &["Last-Event-ID: 41 "], "");
+        assert_eq!(
+            extract::<Header<LastEventId>>(&request).unwrap(),
+            Header(LastEventId("41".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let request = self::request("/", &["Content-Type: application/json; charset=utf-8"], r#"{"q":"a"}"#);
+        assert_eq!(
+            extract::<Json<Search>>(&request).unwrap(),
+            Json(Search { q: "a".to_string(), page: None })
+        );
+
+        let request = self::request("/", &["Content-Type: application/json"], "{");
+        assert!(extract::<Json<Search>>(&request)
+            .unwrap_err()
+            .message
+            .starts_with("Invalid JSON body: EOF while parsing"));
+
+        let request = self::request("/", &["Content-Type: text/plain"], r#"{"q":"a"}"#);
+        assert_eq!(
+            extract::<Json<Search>>(&request).unwrap_err().message,
+            "Expected `Content-Type: application/json`"
+        );
+    }
+
+    #[test]
+    fn test_form_and_bytes() {
+        let request = self::request(
+            "/",
+            &["Content-Type: application/x-www-form-urlencoded"],
+            "q=a%26b&page=3",
+        );
+
+        assert_eq!(
+            extract::<Form<Search>>(&request).unwrap(),
+            Form(Search { q: "a&b".to_string(), page: Some(3) })
+        );
+        assert_eq!(extract::<Bytes>(&request).unwrap(), Bytes(b"q=a%26b&page=3".to_vec()));
+    }
+
+    #[test]
+    fn test_optional_extractors() {
+        let request = self::request("/search", &[], "");
+        assert_eq!(extract::<Option<Query<Search>>>(&request).unwrap(), None);
+        assert_eq!(extract::<Option<Json<Search>>>(&request).unwrap(), None);
+        assert_eq!(extract::<Option<Form<Search>>>(&request).unwrap(), None);
+        assert_eq!(
+            Option::<Path<u32>>::from_request(&request, &Params::new()).unwrap(),
+            None
+        );
+
+        let request = self::request("/search?page=2", &[], "");
+        assert_eq!(
+            extract::<Option<Query<Search>>>(&request).unwrap_err().message,
+            "Invalid query string: missing field `q`"
+        );
+
+        let request = self::request("/", &["Content-Type: application/json"], "{");
+        assert!(extract::<Option<Json<Search>>>(&request)
+            .unwrap_err()
+            .message
+            .starts_with("Invalid JSON body"));
+
+        let request = self::request("/", &[], "q=a");
+        assert_eq!(
+            extract::<Option<Form<Search>>>(&request).unwrap_err().message,
+            "Expected `Content-Type: application/x-www-form-urlencoded`"
+        );
+        assert_eq!(
+            extract::<Option<Path<bool>>>(&request).unwrap_err().message,
+            "Invalid path parameters: cannot parse \"42\" as bool: provided string was not `true` or `false`"
+        );
+    }
+
+    #[test]
+    fn test_rejection_response() {
+        let rejection = Rejection::bad_request("Missing `Host` header");
+
+        assert_eq!(
+            rejection.into_response().to_string(),
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 21\r\n\r\nMissing `Host` header"
+        );
+    }
+}
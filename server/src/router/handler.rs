@@ -0,0 +1,246 @@
+//! # Handler Module
+//!
+//! This module defines the `Handler` trait, which is implemented by everything that can respond
+//! to a route.
+//!
+//! Plain functions and closures taking `(&Request, Params)` are handlers out of the box. Types that
+//! need to carry state between requests (such as a directory to serve files from) can implement
+//! the trait themselves, so the state is set up once when the route is registered.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use std::path::PathBuf;
+//!
+//! use server::{
+//!     request::Request,
+//!     response::IntoResponse,
+//!     router::{Handler, Params, RouteReturn, Router},
+//! };
+//!
+//! struct ShowRoot {
+//!     root: PathBuf,
+//! }
+//!
+//! impl Handler for ShowRoot {
+//!     fn call(&self, request: &Request, _: Params) -> RouteReturn {
+//!         Ok(format!("{} over {}", self.root.display(), request.http_version).into_response())
+//!     }
+//! }
+//!
+//! let greeting = String::from("Hello!");
+//!
+//! let router = Router::new()
+//!     .get("/root", ShowRoot { root: PathBuf::from("/tmp") })
+//!     .get("/hello", move |_: &Request, _: Params| greeting.clone());
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::{request::Request, response::IntoResponse};
+
+use super::{
+    builder::{Params, RouteReturn},
+    extract::FromRequest,
+};
+
+/// Something that can respond to requests matched by a route.
+///
+/// Handlers are shared between the worker threads, so they must be `Send` and `Sync`.
+///
+/// `Args` only tells apart the blanket implementations for functions taking extractors (see the
+/// `extract` module); types implementing the trait themselves use the default.
+pub trait Handler<Args = ()>: Send + Sync + 'static {
+    /// Handles `request`, whose path captured `params`.
+    fn call(&self, request: &Request, params: Params) -> RouteReturn;
+}
+
+impl<F, R> Handler for F
+where
+    F: Fn(&Request, Params) -> R + Send + Sync + 'static,
+    R: IntoResponse,
+{
+    fn call(&self, request: &Request, params: Params) -> RouteReturn {
+        self(request, params).into_result()
+    }
+}
+
+/// Implements `Handler` for functions taking extractors, with and without a leading `&Request`.
+macro_rules! impl_handler {
+    ($($ty:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<F, R, $($ty,)+> Handler<($($ty,)+)> for F
+        where
+            F: Fn($($ty),+) -> R + Send + Sync + 'static,
+            R: IntoResponse,
+            $($ty: FromRequest + 'static,)+
+        {
+            fn call(&self, request: &Request, params: Params) -> RouteReturn {
+                $(
+                    let $ty = match $ty::from_request(request, &params) {
+                        Ok(value) => value,
+                        Err(rejection) => return Err(rejection.into()),
+                    };
+                )+
+
+                self($($ty),+).into_result()
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<F, R, $($ty,)+> Handler<(Request, $($ty,)+)> for F
+        where
+            F: Fn(&Request, $($ty),+) -> R + Send + Sync + 'static,
+            R: IntoResponse,
+            $($ty: FromRequest + 'static,)+
+        {
+            fn call(&self, request: &Request, params: Params) -> RouteReturn {
+                $(
+                    let $ty = match $ty::from_request(request, &params) {
+                        Ok(value) => value,
+                        Err(rejection) => return Err(rejection.into()),
+                    };
+                )+
+
+                self(request, $($ty),+).into_result()
+            }
+        }
+    };
+}
+
+impl_handler!(T1);
+impl_handler!(T1, T2);
+impl_handler!(T1, T2, T3);
+impl_handler!(T1, T2, T3, T4);
+impl_handler!(T1, T2, T3, T4, T5);
+impl_handler!(T1, T2, T3, T4, T5, T6);
+
+/// A `Handler<Args>` turned into a plain `Handler`, so handlers of any kind can be stored together.
+pub(crate) struct Erased<H, Args> {
+    handler: H,
+    args: PhantomData<fn() -> Args>,
+}
+
+impl<H, Args> Erased<H, Args> {
+    pub(crate) fn new(handler: H) -> Erased<H, Args> {
+        Erased {
+            handler,
+            args: PhantomData,
+        }
+    }
+}
+
+impl<H: Handler<Args>, Args: 'static> Handler for Erased<H, Args> {
+    fn call(&self, request: &Request, params: Params) -> RouteReturn {
+        self.handler.call(request, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        response::Response,
+        router::extract::{Bytes, Path, Rejection},
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn request() -> Request {
+        Request {
+            http_version: "HTTP/1.1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn echo(_: &Request, params: Params) -> RouteReturn {
+        Ok(params.get("value").cloned().unwrap_or_default().into_response())
+    }
+
+    struct Counter {
+        count: AtomicUsize,
+    }
+
+    impl Handler for Counter {
+        fn call(&self, _: &Request, _: Params) -> RouteReturn {
+            let count = self.count.fetch_add(1, Ordering::SeqCst) + 1;
+
+            Ok(count.to_string().into_response())
+        }
+    }
+
+    fn text(response: &Response) -> String {
+        String::from_utf8_lossy(response.body.as_bytes().unwrap()).to_string()
+    }
+
+    fn call(handler: &dyn Handler, params: Params) -> String {
+        text(&handler.call(&request(), params).unwrap())
+    }
+
+    #[test]
+    fn test_function_handler() {
+        let params = Params::from([("value".to_string(), "abc".to_string())]);
+
+        assert_eq!(call(&echo, params), "abc");
+    }
+
+    #[test]
+    fn test_closure_handler_captures_state() {
+        let prefix = Arc::new("Hello, ".to_string());
+        let handler = move |_: &Request, params: Params| -> RouteReturn {
+            Ok(format!("{}{}", prefix, params["name"]).into_response())
+        };
+        let params = Params::from([("name".to_string(), "world".to_string())]);
+
+        assert_eq!(call(&handler, params), "Hello, world");
+    }
+
+    #[test]
+    fn test_struct_handler_keeps_state_between_calls() {
+        let counter = Counter {
+            count: AtomicUsize::new(0),
+        };
+
+        assert_eq!(call(&counter, Params::new()), "1");
+        assert_eq!(call(&counter, Params::new()), "2");
+    }
+
+    fn add(Path(id): Path<u32>, Bytes(body): Bytes) -> RouteReturn {
+        Ok(format!("{} {}", id, body.len()).into_response())
+    }
+
+    fn version(request: &Request, Path(id): Path<u32>) -> RouteReturn {
+        Ok(format!("{} {}", request.http_version, id).into_response())
+    }
+
+    fn call_with<Args: 'static>(handler: impl Handler<Args>, params: &[(&str, &str)]) -> Response {
+        let params = params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        Erased::new(handler).call(&request(), params).unwrap()
+    }
+
+    #[test]
+    fn test_extractor_handlers() {
+        assert_eq!(text(&call_with(add, &[("id", "7")])), "7 0");
+        assert_eq!(text(&call_with(version, &[("id", "7")])), "HTTP/1.1 7");
+    }
+
+    #[test]
+    fn test_rejected_extractor_is_an_error() {
+        let error = Erased::new(add)
+            .call(&request(), Params::from([("id".to_string(), "x".to_string())]))
+            .unwrap_err();
+        let rejection = error.downcast_ref::<Rejection>().unwrap();
+
+        assert_eq!(rejection.status, crate::http::code::HttpCode::BadRequest);
+        assert_eq!(
+            rejection.message,
+            "Invalid path parameters: cannot parse \"x\" as u32: invalid digit found in string"
+        );
+    }
+}
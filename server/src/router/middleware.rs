@@ -0,0 +1,205 @@
+//! # Middleware Module
+//!
+//! This module defines the `Middleware` trait, for behavior shared by many routes such as
+//! logging, authentication or compression.
+//!
+//! Middleware wraps handlers like the layers of an onion. Each middleware receives the request
+//! and a `Next` continuation for the rest of the chain, and can:
+//!
+//! - inspect the request and call `next.run` to continue,
+//! - short-circuit by returning its own response without calling `next`,
+//! - rewrite the response returned by `next`.
+//!
+//! Middleware is applied either to every request handled by a router, with `Router::layer`, or
+//! to a single route, by wrapping its handler with `HandlerExt::with`. Global middleware runs
+//! first, in registration order, followed by the route's own middleware.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     http::code::HttpCode,
+//!     request::Request,
+//!     response::IntoResponse,
+//!     router::{HandlerExt, Next, Params, RouteReturn, Router},
+//! };
+//!
+//! fn hello(_: &Request, _: Params) -> RouteReturn {
+//!     Ok("Hello!".into_response())
+//! }
+//!
+//! fn log(request: &Request, params: Params, next: Next) -> RouteReturn {
+//!     let response = next.run(request, params);
+//!     println!("{:?} {:?}", request.method, request.url);
+//!
+//!     response
+//! }
+//!
+//! fn require_agent(request: &Request, params: Params, next: Next) -> RouteReturn {
+//!     match request.user_agent {
+//!         Some(_) => next.run(request, params),
+//!         None => Ok(HttpCode::Forbidden.into_response()),
+//!     }
+//! }
+//!
+//! let router = Router::new()
+//!     .layer(log)
+//!     .get("/hello", hello.with(require_agent));
+//! ```
+
+use crate::request::Request;
+
+use super::{
+    builder::{Params, RouteReturn},
+    handler::{Erased, Handler},
+};
+
+/// Behavior wrapped around handlers.
+///
+/// Functions and closures taking `(&Request, Params, Next)` are middleware out of the box.
+pub trait Middleware: Send + Sync + 'static {
+    /// Handles `request`, usually by passing it on to `next`.
+    fn call(&self, request: &Request, params: Params, next: Next<'_>) -> RouteReturn;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&Request, Params, Next<'_>) -> RouteReturn + Send + Sync + 'static,
+{
+    fn call(&self, request: &Request, params: Params, next: Next<'_>) -> RouteReturn {
+        self(request, params, next)
+    }
+}
+
+/// The remainder of a middleware chain: the middleware that has yet to run, then the handler.
+pub struct Next<'a> {
+    middleware: &'a [&'a dyn Middleware],
+    handler: &'a dyn Handler,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middleware: &'a [&'a dyn Middleware], handler: &'a dyn Handler) -> Next<'a> {
+        Next {
+            middleware,
+            handler,
+        }
+    }
+
+    /// Runs the rest of the chain and returns its response.
+    pub fn run(self, request: &Request, params: Params) -> RouteReturn {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => middleware.call(request, params, Next::new(rest, self.handler)),
+            None => self.handler.call(request, params),
+        }
+    }
+}
+
+/// A handler wrapped in a middleware, created with `HandlerExt::with`.
+pub struct Layered<H, Args> {
+    handler: Erased<H, Args>,
+    middleware: Box<dyn Middleware>,
+}
+
+impl<H: Handler<Args>, Args: 'static> Handler for Layered<H, Args> {
+    fn call(&self, request: &Request, params: Params) -> RouteReturn {
+        self.middleware
+            .call(request, params, Next::new(&[], &self.handler))
+    }
+}
+
+/// Adds per-route middleware to handlers.
+pub trait HandlerExt<Args>: Handler<Args> + Sized {
+    /// Wraps this handler in `middleware`.
+    ///
+    /// Calls can be chained; the middleware added last runs first.
+    fn with(self, middleware: impl Middleware) -> Layered<Self, Args> {
+        Layered {
+            handler: Erased::new(self),
+            middleware: Box::new(middleware),
+        }
+    }
+}
+
+impl<H: Handler<Args>, Args> HandlerExt<Args> for H {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{IntoResponse, Response};
+
+    fn request(user_agent: Option<&str>) -> Request {
+        Request {
+            user_agent: user_agent.map(|s| s.to_string()),
+            http_version: "HTTP/1.1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn text(response: &Response) -> String {
+        String::from_utf8_lossy(response.body.as_bytes().unwrap()).to_string()
+    }
+
+    fn handler(_: &Request, params: Params) -> RouteReturn {
+        Ok(format!("handler{}", params.get("trace").cloned().unwrap_or_default()).into_response())
+    }
+
+    fn outer(request: &Request, mut params: Params, next: Next) -> RouteReturn {
+        params.insert("trace".to_string(), " <- outer".to_string());
+        let response = next.run(request, params)?;
+
+        Ok(format!("outer({})", text(&response)).into_response())
+    }
+
+    fn inner(request: &Request, params: Params, next: Next) -> RouteReturn {
+        let response = next.run(request, params)?;
+
+        Ok(format!("inner({})", text(&response)).into_response())
+    }
+
+    fn require_agent(request: &Request, params: Params, next: Next) -> RouteReturn {
+        match request.user_agent {
+            Some(_) => next.run(request, params),
+            None => Ok("forbidden".into_response()),
+        }
+    }
+
+    #[test]
+    fn test_chain_runs_in_order() {
+        let middleware: Vec<&dyn Middleware> = vec![&outer, &inner];
+        let response = Next::new(&middleware, &handler)
+            .run(&request(None), Params::new())
+            .unwrap();
+
+        assert_eq!(text(&response), "outer(inner(handler <- outer))");
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let handler = handler.with(require_agent);
+
+        assert_eq!(text(&handler.call(&request(None), Params::new()).unwrap()), "forbidden");
+        assert_eq!(text(&handler.call(&request(Some("curl")), Params::new()).unwrap()), "handler");
+    }
+
+    #[test]
+    fn test_with_chains_outside_in() {
+        let handler = handler.with(inner).with(outer);
+
+        assert_eq!(
+            text(&handler.call(&request(None), Params::new()).unwrap()),
+            "outer(inner(handler <- outer))"
+        );
+    }
+
+    #[test]
+    fn test_closure_middleware() {
+        let suffix = String::from("!");
+        let handler = handler.with(move |request: &Request, params: Params, next: Next| {
+            let response = next.run(request, params)?;
+
+            Ok((text(&response) + &suffix).into_response())
+        });
+
+        assert_eq!(text(&handler.call(&request(None), Params::new()).unwrap()), "handler!");
+    }
+}
@@ -0,0 +1,18 @@
+pub mod builder;
+pub mod compression;
+pub mod extract;
+pub mod handler;
+pub mod middleware;
+pub mod policy;
+pub mod redirect;
+pub mod state;
+pub mod tree;
+
+pub use builder::Router;
+pub use builder::{Params, RouteReturn};
+pub use compression::Compression;
+pub use handler::Handler;
+pub use middleware::{HandlerExt, Middleware, Next};
+pub use policy::SlashPolicy;
+pub use state::StateMap;
+pub use tree::RouteTree;
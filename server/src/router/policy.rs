@@ -0,0 +1,185 @@
+//! # Policy Module
+//!
+//! This module defines `SlashPolicy`, which decides how a `Router` treats request paths that
+//! only differ from a route by their slashes:
+//!
+//! - a trailing slash the route does not have, or a missing one that it has (`/echo/abc/` for
+//!   the route `/echo/:str`),
+//! - repeated slashes (`//echo//abc`).
+//!
+//! Each case can be served as if the path were canonical (`Ignore`, the default), rejected like
+//! any unknown path (`Strict`), or answered with a `308 Permanent Redirect` to the canonical path
+//! (`Redirect`), so that every resource has a single URL for caches and relative links.
+//!
+//! The canonical path of a request is its own path, with repeated slashes collapsed and a
+//! trailing slash if and only if the matching route was registered with one, e.g. `/docs/`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     http::{code::HttpCode, method::HttpMethod},
+//!     request::Request,
+//!     router::{Params, Router, SlashPolicy},
+//!     url::parser::Url,
+//! };
+//!
+//! let router = Router::new()
+//!     .trailing_slash(SlashPolicy::Redirect)
+//!     .duplicate_slashes(SlashPolicy::Strict)
+//!     .get("/echo/:str", |_: &Request, _: Params| "echo");
+//!
+//! let mut request = Request {
+//!     method: Some(HttpMethod::GET),
+//!     url: Some(Url::new("/echo/abc/?x=1").unwrap()),
+//!     ..Default::default()
+//! };
+//! let response = router.handle(&mut request);
+//! assert_eq!(response.status_code, HttpCode::PermanentRedirect);
+//! assert!(response.head().contains("Location: /echo/abc?x=1\r\n"));
+//!
+//! request.url = Some(Url::new("//echo//abc").unwrap());
+//! assert_eq!(router.handle(&mut request).status_code, HttpCode::NotFound);
+//! ```
+
+/// How a router treats paths that differ from the matching route by their slashes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SlashPolicy {
+    /// The path does not match the route.
+    Strict,
+
+    /// The client is redirected to the canonical path with `308 Permanent Redirect`.
+    Redirect,
+
+    /// The path matches the route as if it were canonical.
+    #[default]
+    Ignore,
+}
+
+/// The outcome of checking a request path against the route it matched.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PathCheck {
+    /// The route handles the path.
+    Accept,
+
+    /// The client should be redirected to this canonical path.
+    Redirect(String),
+
+    /// The path does not match the route.
+    Reject,
+}
+
+/// Checks the raw request `path` against `route`, the pattern that matched it.
+pub(crate) fn check_path(
+    path: &str,
+    route: &str,
+    trailing_slash: SlashPolicy,
+    duplicate_slashes: SlashPolicy,
+) -> PathCheck {
+    let mut canonical = path.to_string();
+    let mut redirect = false;
+
+    if path.contains("//") {
+        match duplicate_slashes {
+            SlashPolicy::Strict => return PathCheck::Reject,
+            SlashPolicy::Redirect => {
+                canonical = collapse_slashes(path);
+                redirect = true;
+            }
+            SlashPolicy::Ignore => {}
+        }
+    }
+
+    // The root has no trailing slash to speak of, and a route `/` mounted under a prefix
+    // stands for the prefix itself.
+    let wants_slash = route != "/" && route.ends_with('/');
+    let has_slash = !canonical.trim_matches('/').is_empty() && canonical.ends_with('/');
+    if wants_slash != has_slash {
+        match trailing_slash {
+            SlashPolicy::Strict => return PathCheck::Reject,
+            SlashPolicy::Redirect => {
+                canonical = if wants_slash {
+                    format!("{}/", canonical)
+                } else {
+                    canonical.trim_end_matches('/').to_string()
+                };
+                redirect = true;
+            }
+            SlashPolicy::Ignore => {}
+        }
+    }
+
+    if redirect {
+        // A location starting with `//` is a network-path reference to another host, so leading
+        // slashes are collapsed even when duplicate slashes are otherwise ignored.
+        PathCheck::Redirect(format!("/{}", canonical.trim_start_matches('/')))
+    } else {
+        PathCheck::Accept
+    }
+}
+
+/// Replaces every run of slashes in `path` with a single one.
+fn collapse_slashes(path: &str) -> String {
+    let mut collapsed = String::with_capacity(path.len());
+    for c in path.chars() {
+        if !(c == '/' && collapsed.ends_with('/')) {
+            collapsed.push(c);
+        }
+    }
+
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SlashPolicy::*;
+
+    #[test]
+    fn test_trailing_slash() {
+        assert_eq!(check_path("/a/b/", "/a/:x", Ignore, Ignore), PathCheck::Accept);
+        assert_eq!(check_path("/a/b/", "/a/:x", Strict, Ignore), PathCheck::Reject);
+        assert_eq!(
+            check_path("/a/b/", "/a/:x", Redirect, Ignore),
+            PathCheck::Redirect("/a/b".to_string())
+        );
+        assert_eq!(
+            check_path("/docs", "/docs/", Redirect, Ignore),
+            PathCheck::Redirect("/docs/".to_string())
+        );
+        assert_eq!(check_path("/docs/", "/docs/", Strict, Strict), PathCheck::Accept);
+        assert_eq!(check_path("/", "/", Strict, Strict), PathCheck::Accept);
+        assert_eq!(check_path("/api", "/", Strict, Strict), PathCheck::Accept);
+    }
+
+    #[test]
+    fn test_duplicate_slashes() {
+        assert_eq!(check_path("//a//b", "/a/b", Strict, Ignore), PathCheck::Accept);
+        assert_eq!(check_path("//a//b", "/a/b", Ignore, Strict), PathCheck::Reject);
+        assert_eq!(
+            check_path("//a//b//", "/a/b", Redirect, Redirect),
+            PathCheck::Redirect("/a/b".to_string())
+        );
+        assert_eq!(
+            check_path("//a//b//", "/a/b", Ignore, Redirect),
+            PathCheck::Redirect("/a/b/".to_string())
+        );
+        assert_eq!(check_path("//", "/", Strict, Ignore), PathCheck::Accept);
+    }
+
+    #[test]
+    fn test_redirect_never_leaves_the_host() {
+        assert_eq!(
+            check_path("//evil.com/", "/:x", Redirect, Ignore),
+            PathCheck::Redirect("/evil.com".to_string())
+        );
+        assert_eq!(
+            check_path("///evil.com//a/", "/*rest", Redirect, Ignore),
+            PathCheck::Redirect("/evil.com//a".to_string())
+        );
+        assert_eq!(
+            check_path("//evil.com", "/:x/", Redirect, Ignore),
+            PathCheck::Redirect("/evil.com/".to_string())
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! # Redirect Module
+//!
+//! This module defines the `Redirect` handler, which answers every request with a redirect to a
+//! target built from the request path. It backs `Router::redirect`, for moved routes:
+//!
+//! ```text
+//! /blog/:year/*path  ->  /archive/:year/*path
+//! ```
+//!
+//! The target is a route pattern whose parameters are filled in with the values captured from
+//! the request path, percent-encoded again. A catch-all is encoded segment by segment, as it
+//! appeared in the request, so an encoded slash (`%2F`) within a segment stays encoded.
+//! Parameters keep their names: `:year` in the target takes the value of `:year` in the route.
+//! The query string of the request is carried over.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::{
+//!     http::code::HttpCode,
+//!     request::Request,
+//!     response::redirect::RedirectKind,
+//!     router::{redirect::Redirect, Router},
+//!     url::parser::Url,
+//! };
+//!
+//! let router = Router::new()
+//!     .redirect("/old/*path", "/new/*path", RedirectKind::PermanentPreservingMethod)
+//!     .get("/home", Redirect::new("/", RedirectKind::Temporary));
+//!
+//! let mut request = Request {
+//!     url: Some(Url::new("/old/docs/caf%C3%A9?lang=fr").unwrap()),
+//!     method: Some(server::http::method::HttpMethod::POST),
+//!     ..Default::default()
+//! };
+//! let response = router.handle(&mut request);
+//! assert_eq!(response.status_code, HttpCode::PermanentRedirect);
+//! assert!(response.head().contains("Location: /new/docs/caf%C3%A9?lang=fr\r\n"));
+//! ```
+
+use crate::{
+    request::Request,
+    response::{redirect::RedirectKind, Response},
+    url::{
+        pattern::{RoutePattern, Segment},
+        percent::{percent_decode, percent_encode},
+    },
+};
+
+use super::{
+    builder::{Params, RouteReturn},
+    handler::Handler,
+};
+
+/// A handler redirecting to a target pattern; see the module documentation.
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    target: RoutePattern,
+    kind: RedirectKind,
+}
+
+impl Redirect {
+    /// Creates a handler redirecting to `target`, with the status code of `kind`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` is not a valid `RoutePattern`.
+    pub fn new(target: &str, kind: RedirectKind) -> Redirect {
+        let target = RoutePattern::parse(target)
+            .unwrap_or_else(|e| panic!("Failed to create redirect: {}", e));
+
+        Redirect { target, kind }
+    }
+
+    /// Returns the names of the parameters the target needs.
+    pub(crate) fn param_names(&self) -> impl Iterator<Item = &str> {
+        self.target.segments().iter().filter_map(|s| match &s.segment {
+            Segment::Static(_) => None,
+            Segment::Param { name, .. } | Segment::CatchAll(name) => Some(name.as_str()),
+        })
+    }
+
+    /// Builds the location for a request whose path, split into the percent-encoded
+    /// `path_segments`, captured `params`.
+    ///
+    /// Parameters missing from `params`, such as skipped optional segments, are left out.
+    fn location(&self, path_segments: &[&str], params: &Params) -> String {
+        let segments = self
+            .target
+            .segments()
+            .iter()
+            .filter_map(|s| match &s.segment {
+                Segment::Static(value) => Some(value.clone()),
+                Segment::Param { name, .. } => params.get(name).map(|v| percent_encode(v)),
+                Segment::CatchAll(name) => {
+                    params.get(name).map(|v| encode_catch_all(v, path_segments))
+                }
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let trailing_slash = if self.target.as_str().ends_with('/') && !segments.is_empty() {
+            "/"
+        } else {
+            ""
+        };
+
+        format!("/{}{}", segments.join("/"), trailing_slash)
+    }
+}
+
+impl Handler for Redirect {
+    fn call(&self, request: &Request, params: Params) -> RouteReturn {
+        let path = request.url.as_ref().map(|url| url.normalized_path()).unwrap_or_default();
+        let path_segments = path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+
+        let mut location = self.location(&path_segments, &params);
+        if let Some(query) = request.url.as_ref().and_then(|url| url.query.as_ref()) {
+            if !query.is_empty() {
+                location = format!("{}?{}", location, query);
+            }
+        }
+
+        Ok(Response::redirect(&location, self.kind).build())
+    }
+}
+
+/// Percent-encodes the catch-all `value` segment by segment.
+///
+/// The value is the decoded segments it captured, joined with `/`, so the segments are taken
+/// back from the end of the request path rather than by splitting the value on `/`. Without a
+/// matching suffix, as when the handler is called with made-up params, the value is split.
+fn encode_catch_all(value: &str, path_segments: &[&str]) -> String {
+    let decoded = path_segments
+        .iter()
+        .map(|s| percent_decode(s).unwrap_or_else(|_| s.to_string()))
+        .collect::<Vec<_>>();
+
+    let captured = (1..=decoded.len())
+        .map(|count| &decoded[decoded.len() - count..])
+        .find(|segments| segments.join("/") == value);
+
+    match captured {
+        Some(segments) => segments.iter().map(|s| percent_encode(s)).collect::<Vec<_>>().join("/"),
+        None => value.split('/').map(percent_encode).collect::<Vec<_>>().join("/"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{code::HttpCode, method::HttpMethod},
+        router::Router,
+        url::parser::Url,
+    };
+
+    fn follow(router: &Router, method: HttpMethod, url: &str) -> (HttpCode, String) {
+        let mut request = Request {
+            method: Some(method),
+            url: Some(Url::new(url).unwrap()),
+            http_version: "HTTP/1.1".to_string(),
+            ..Default::default()
+        };
+        let response = router.handle(&mut request);
+        let location = response
+            .headers
+            .iter()
+            .flatten()
+            .map(|header| header.to_string())
+            .find_map(|header| header.strip_prefix("Location: ").map(str::to_string))
+            .unwrap_or_default();
+
+        (response.status_code, location)
+    }
+
+    #[test]
+    fn test_rule_substitutes_params() {
+        let router = Router::new()
+            .redirect("/blog/:year/*path", "/archive/:year/*path", RedirectKind::Permanent)
+            .redirect("/u/:id/:tab?", "/users/:id/:tab?/", RedirectKind::Temporary);
+
+        assert_eq!(
+            follow(&router, HttpMethod::GET, "/blog/2024/a%20b/c%2Fd?x=1"),
+            (HttpCode::MovedPermanently, "/archive/2024/a%20b/c%2Fd?x=1".to_string())
+        );
+        assert_eq!(
+            follow(&router, HttpMethod::GET, "/blog/2024//a%2F/b/./c/"),
+            (HttpCode::MovedPermanently, "/archive/2024/a%2F/b/c".to_string())
+        );
+        assert_eq!(
+            follow(&router, HttpMethod::DELETE, "/u/7"),
+            (HttpCode::Found, "/users/7/".to_string())
+        );
+        assert_eq!(
+            follow(&router, HttpMethod::GET, "/u/7/posts"),
+            (HttpCode::Found, "/users/7/posts/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_specific_routes_take_precedence() {
+        let router = Router::new()
+            .redirect("/docs/*path", "/manual/*path", RedirectKind::SeeOther)
+            .get("/docs/index", |_: &Request, _: Params| "index");
+
+        assert_eq!(follow(&router, HttpMethod::GET, "/docs/index").0, HttpCode::Ok);
+        assert_eq!(
+            follow(&router, HttpMethod::POST, "/docs/intro"),
+            (HttpCode::SeeOther, "/manual/intro".to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to register redirect: target \"/new/:slug\" uses `slug`")]
+    fn test_rule_with_unknown_param_panics() {
+        let _ = Router::new().redirect("/old/:id", "/new/:slug", RedirectKind::Permanent);
+    }
+}
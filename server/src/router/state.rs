@@ -0,0 +1,193 @@
+//! # State Module
+//!
+//! This module defines `StateMap`, a typed container for application state shared between
+//! handlers, such as configuration, caches or connection pools.
+//!
+//! State is registered on the `Router` with `Router::with_state`, one value per type. The router
+//! attaches it to every request it dispatches, and handlers look it up by type with
+//! `Request::state`. Looking up a type that was never registered is reported as a
+//! `MissingStateError` naming the type, instead of a panic.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use server::{
+//!     request::Request,
+//!     response::IntoResponse,
+//!     router::{Params, RouteReturn, Router},
+//! };
+//!
+//! struct Config {
+//!     greeting: String,
+//! }
+//!
+//! fn hello(request: &Request, _: Params) -> RouteReturn {
+//!     let config = request.state::<Config>()?;
+//!
+//!     Ok(config.greeting.clone().into_response())
+//! }
+//!
+//! let router = Router::new()
+//!     .with_state(Arc::new(Config { greeting: "Hello!".to_string() }))
+//!     .get("/hello", hello);
+//! ```
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    sync::Arc,
+};
+
+/// A map from types to shared values of that type.
+///
+/// Cloning a `StateMap` is cheap: the values themselves are reference-counted.
+#[derive(Clone, Default)]
+pub struct StateMap {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl StateMap {
+    /// Creates an empty state map.
+    pub fn new() -> StateMap {
+        StateMap::default()
+    }
+
+    /// Stores `value`, replacing any previous value of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: Arc<T>) {
+        self.values.insert(TypeId::of::<T>(), value);
+    }
+
+    /// Copies every value of `other` into this map, replacing values of the same type.
+    pub fn extend(&mut self, other: &StateMap) {
+        self.values
+            .extend(other.values.iter().map(|(k, v)| (*k, Arc::clone(v))));
+    }
+
+    /// Returns the value of type `T`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Arc<T>)`: A shared reference to the stored value.
+    /// - `Err(MissingStateError)`: If no value of type `T` was stored.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Result<Arc<T>, MissingStateError> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| Arc::clone(value).downcast::<T>().ok())
+            .ok_or(MissingStateError {
+                type_name: type_name::<T>(),
+            })
+    }
+
+    /// Returns `true` if a value of type `T` was stored.
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no values were stored.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl std::fmt::Debug for StateMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateMap")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+/// Returned when a handler asks for state that was not registered on the router.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MissingStateError {
+    /// The name of the requested type.
+    pub type_name: &'static str,
+}
+
+impl Display for MissingStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "No state of type `{}` is registered; add it with `Router::with_state`",
+            self.type_name
+        )
+    }
+}
+
+impl Error for MissingStateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Config {
+        name: &'static str,
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut state = StateMap::new();
+        state.insert(Arc::new(Config { name: "test" }));
+        state.insert(Arc::new(42u32));
+
+        assert_eq!(state.get::<Config>().unwrap().name, "test");
+        assert_eq!(*state.get::<u32>().unwrap(), 42);
+        assert!(state.contains::<Config>());
+        assert_eq!(state.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_replaces_same_type() {
+        let mut state = StateMap::new();
+        state.insert(Arc::new(1u32));
+        state.insert(Arc::new(2u32));
+
+        assert_eq!(*state.get::<u32>().unwrap(), 2);
+        assert_eq!(state.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_state() {
+        let state = StateMap::new();
+        let error = state.get::<Config>().unwrap_err();
+
+        assert!(state.is_empty());
+        assert!(error.type_name.ends_with("Config"));
+        assert!(error.to_string().starts_with("No state of type `"));
+        assert!(error.to_string().ends_with("Config` is registered; add it with `Router::with_state`"));
+    }
+
+    #[test]
+    fn test_extend_replaces_same_type() {
+        let mut outer = StateMap::new();
+        outer.insert(Arc::new(1u32));
+        outer.insert(Arc::new(Config { name: "outer" }));
+
+        let mut inner = StateMap::new();
+        inner.insert(Arc::new(2u32));
+
+        outer.extend(&inner);
+        assert_eq!(*outer.get::<u32>().unwrap(), 2);
+        assert_eq!(outer.get::<Config>().unwrap().name, "outer");
+    }
+
+    #[test]
+    fn test_clone_shares_values() {
+        let mut state = StateMap::new();
+        let config = Arc::new(Config { name: "shared" });
+        state.insert(Arc::clone(&config));
+
+        let cloned = state.clone();
+        assert!(Arc::ptr_eq(&cloned.get::<Config>().unwrap(), &config));
+    }
+}
@@ -0,0 +1,665 @@
+//! # Route Tree Module
+//!
+//! This module defines the `RouteTree` struct, a precompiled prefix tree of route patterns.
+//!
+//! Routes are inserted once, at startup, using the `RoutePattern` syntax. Lookups then walk the
+//! tree one path segment at a time instead of trying every pattern in turn. When every segment
+//! has a single matching child, their cost depends on the length of the path rather than on the
+//! number of routes. Backtracking out of a failed branch, or comparing several constrained
+//! parameters that accept the same segment, can visit more of the tree.
+//!
+//! ## Precedence
+//!
+//! At every segment, children are tried in order of specificity: the static child first, then
+//! constrained parameters, then the unconstrained parameter, and finally a catch-all. If a branch
+//! fails further down, the lookup backtracks and tries the next child.
+//!
+//! Constrained parameters are equally specific, so every one accepting the segment is tried, and
+//! the rest of the path decides between them: with `/:a<uint>/:b` and `/:a<int>/x`, the path
+//! `/1/x` matches the second route, whose next segment is static. Remaining ties go to the route
+//! skipping fewer optional segments, then to the route registered first. This yields the same
+//! left-to-right precedence as `RoutePattern::best_match`.
+//!
+//! ## Conflicts
+//!
+//! Two routes conflict when they match exactly the same paths, e.g. `/user/:id` and `/user/:name`,
+//! or `/docs/intro` and the expansion of `/docs/:lang?/intro` that skips `lang`. Conflicts are
+//! reported by `RouteTree::insert` instead of silently shadowing a route.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::router::tree::RouteTree;
+//! use server::url::Url;
+//!
+//! let mut tree = RouteTree::new();
+//! tree.insert("/user/:id", "user").unwrap();
+//! tree.insert("/user/me", "me").unwrap();
+//! tree.insert("/files/*path", "files").unwrap();
+//!
+//! let found = tree.find(&Url::new("/user/42").unwrap()).unwrap();
+//! assert_eq!(*found.value, "user");
+//! assert_eq!(found.params.get("id").unwrap(), "42");
+//!
+//! assert_eq!(*tree.find(&Url::new("/user/me").unwrap()).unwrap().value, "me");
+//! assert!(tree.insert("/user/:name", "conflict").is_err());
+//! ```
+
+use std::{cmp::Ordering, collections::HashMap, error::Error, fmt::Display};
+
+use crate::url::{
+    pattern::{Constraint, PatternError, PatternSegment, RoutePattern, Segment},
+    Url,
+};
+
+/// Error returned when a route cannot be inserted into a `RouteTree`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InsertError {
+    /// The route pattern is invalid.
+    Pattern(PatternError),
+
+    /// The route matches exactly the same paths as an existing route.
+    Conflict {
+        /// The pattern being inserted.
+        pattern: String,
+
+        /// The previously inserted pattern it conflicts with.
+        existing: String,
+    },
+}
+
+impl Display for InsertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InsertError::Pattern(e) => write!(f, "{}", e),
+            InsertError::Conflict { pattern, existing } => write!(
+                f,
+                "Route {:?} conflicts with existing route {:?}",
+                pattern, existing
+            ),
+        }
+    }
+}
+
+impl Error for InsertError {}
+
+impl From<PatternError> for InsertError {
+    fn from(e: PatternError) -> Self {
+        InsertError::Pattern(e)
+    }
+}
+
+/// A successful lookup in a `RouteTree`.
+#[derive(Debug, PartialEq)]
+pub struct Match<'a, T> {
+    /// The value registered for the matching route.
+    pub value: &'a T,
+
+    /// The pattern of the matching route, as it was inserted.
+    pub pattern: &'a str,
+
+    /// The captured, percent-decoded parameters.
+    pub params: HashMap<String, String>,
+}
+
+/// A route ending at a node: which value it maps to and how to name the captures.
+#[derive(Debug)]
+struct Leaf {
+    route: usize,
+    param_names: Vec<String>,
+    /// The number of optional segments this expansion of the route skips.
+    skipped: usize,
+}
+
+/// A leaf reached by `Node::find`, with what was captured on the way and how specifically.
+///
+/// Both lists are built while returning from the recursion, so they are in reverse path order.
+struct Found<'a> {
+    leaf: &'a Leaf,
+    captures: Vec<String>,
+    ranks: Vec<u8>,
+}
+
+impl<'a> Found<'a> {
+    fn new(leaf: &'a Leaf) -> Found<'a> {
+        Found {
+            leaf,
+            captures: Vec::new(),
+            ranks: Vec::new(),
+        }
+    }
+
+    /// Records that the segment before the ones already found matched with `rank`.
+    fn after(mut self, rank: u8, capture: Option<&String>) -> Found<'a> {
+        self.ranks.push(rank);
+        self.captures.extend(capture.cloned());
+
+        self
+    }
+
+    /// Orders two leaves found for the same segments like `RoutePattern::best_match`: by the
+    /// rank of each segment from left to right, then by skipped optional segments, then by
+    /// registration order.
+    fn precedence(&self, other: &Found) -> Ordering {
+        self.ranks
+            .iter()
+            .rev()
+            .cmp(other.ranks.iter().rev())
+            .then(self.leaf.skipped.cmp(&other.leaf.skipped))
+            .then(self.leaf.route.cmp(&other.leaf.route))
+    }
+}
+
+#[derive(Debug)]
+struct ParamChild {
+    constraint: Option<Constraint>,
+    node: Node,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    statics: HashMap<String, Node>,
+    /// Constrained parameters in registration order, followed by at most one unconstrained one.
+    params: Vec<ParamChild>,
+    catch_all: Option<Leaf>,
+    leaf: Option<Leaf>,
+}
+
+/// A prefix tree mapping route patterns to values.
+///
+/// See the module documentation for the precedence and conflict rules.
+#[derive(Debug)]
+pub struct RouteTree<T> {
+    root: Node,
+    routes: Vec<(String, T)>,
+}
+
+impl<T> RouteTree<T> {
+    /// Creates an empty tree.
+    pub fn new() -> RouteTree<T> {
+        RouteTree {
+            root: Node::default(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Compiles `pattern` and inserts it into the tree, mapped to `value`.
+    ///
+    /// Patterns with optional segments are expanded into every combination of present and
+    /// skipped segments, each of which must be free of conflicts.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())`: If the route was inserted.
+    /// - `Err(InsertError)`: If the pattern is invalid or conflicts with an existing route.
+    ///   The tree is left unchanged in that case.
+    pub fn insert(&mut self, pattern: &str, value: T) -> Result<(), InsertError> {
+        let compiled = RoutePattern::parse(pattern)?;
+        let route = self.routes.len();
+        let expansions = Self::expand(compiled.segments());
+
+        // Check every expansion before touching the tree, so a failed insert has no effect.
+        for segments in &expansions {
+            if let Some(existing) = self.root.find_conflict(segments) {
+                return Err(InsertError::Conflict {
+                    pattern: pattern.to_string(),
+                    existing: self.routes[existing].0.clone(),
+                });
+            }
+        }
+
+        // Two expansions of the same pattern collide when optional segments are ambiguous,
+        // as in `/:a?/:b?`.
+        for (i, segments) in expansions.iter().enumerate() {
+            if expansions[..i].iter().any(|other| Self::same_shape(other, segments)) {
+                return Err(InsertError::Conflict {
+                    pattern: pattern.to_string(),
+                    existing: pattern.to_string(),
+                });
+            }
+        }
+
+        for segments in expansions {
+            // Every skipped optional segment shortens the expansion by one.
+            let skipped = compiled.segments().len() - segments.len();
+            self.root.insert(&segments, route, skipped);
+        }
+
+        self.routes.push((pattern.to_string(), value));
+
+        Ok(())
+    }
+
+    /// Looks up the route matching the normalized, percent-decoded path of `url`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(Match)`: The most specific matching route and its captured parameters.
+    /// - `None`: If no route matches.
+    pub fn find(&self, url: &Url) -> Option<Match<'_, T>> {
+        self.find_segments(&url.segments())
+    }
+
+    /// Looks up the route matching already split and decoded path `segments`.
+    pub fn find_segments(&self, segments: &[String]) -> Option<Match<'_, T>> {
+        self.lookup(segments, false)
+    }
+
+    /// Looks up the route matching `segments` like `find_segments`, except that static segments
+    /// also match when they differ in ASCII case. An exact match takes precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::router::tree::RouteTree;
+    ///
+    /// let mut tree = RouteTree::new();
+    /// tree.insert("/Users/:name", "users").unwrap();
+    ///
+    /// let segments = ["users".to_string(), "Ada".to_string()];
+    /// let found = tree.find_segments_ignoring_case(&segments).unwrap();
+    /// assert_eq!(found.params.get("name").unwrap(), "Ada");
+    /// assert!(tree.find_segments(&segments).is_none());
+    /// ```
+    pub fn find_segments_ignoring_case(&self, segments: &[String]) -> Option<Match<'_, T>> {
+        self.lookup(segments, true)
+    }
+
+    fn lookup(&self, segments: &[String], ignore_case: bool) -> Option<Match<'_, T>> {
+        let found = self.root.find(segments, ignore_case)?;
+        let (pattern, value) = &self.routes[found.leaf.route];
+
+        let params = found
+            .leaf
+            .param_names
+            .iter()
+            .cloned()
+            .zip(found.captures.into_iter().rev())
+            .collect();
+
+        Some(Match {
+            value,
+            pattern,
+            params,
+        })
+    }
+
+    /// Iterates over the inserted patterns and their values, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.routes.iter().map(|(pattern, value)| (pattern.as_str(), value))
+    }
+
+    /// Returns the number of inserted routes.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns `true` if no routes have been inserted.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Expands optional segments into every combination of required segments.
+    fn expand(segments: &[PatternSegment]) -> Vec<Vec<Segment>> {
+        let mut expansions = vec![Vec::new()];
+
+        for pattern_segment in segments {
+            let mut next = Vec::with_capacity(expansions.len() * 2);
+
+            for expansion in &expansions {
+                let mut taken = expansion.clone();
+                taken.push(pattern_segment.segment.clone());
+                next.push(taken);
+
+                if pattern_segment.optional {
+                    next.push(expansion.clone());
+                }
+            }
+
+            expansions = next;
+        }
+
+        expansions
+    }
+
+    fn same_shape(a: &[Segment], b: &[Segment]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Node::same_kind(a, b))
+    }
+}
+
+impl<T> Default for RouteTree<T> {
+    fn default() -> Self {
+        RouteTree::new()
+    }
+}
+
+impl Node {
+    /// Returns the route index of an existing route with the same shape as `segments`, if any.
+    fn find_conflict(&self, segments: &[Segment]) -> Option<usize> {
+        let Some((first, rest)) = segments.split_first() else {
+            return self.leaf.as_ref().map(|leaf| leaf.route);
+        };
+
+        match first {
+            Segment::Static(literal) => self.statics.get(literal)?.find_conflict(rest),
+            Segment::Param { constraint, .. } => self
+                .params
+                .iter()
+                .find(|child| child.constraint == *constraint)?
+                .node
+                .find_conflict(rest),
+            Segment::CatchAll(_) => self.catch_all.as_ref().map(|leaf| leaf.route),
+        }
+    }
+
+    fn same_kind(a: &Segment, b: &Segment) -> bool {
+        match (a, b) {
+            (Segment::Static(a), Segment::Static(b)) => a == b,
+            (Segment::Param { constraint: a, .. }, Segment::Param { constraint: b, .. }) => a == b,
+            (Segment::CatchAll(_), Segment::CatchAll(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Inserts a conflict-free expansion of a route.
+    fn insert(&mut self, segments: &[Segment], route: usize, skipped: usize) {
+        let mut node = self;
+        let mut param_names = Vec::new();
+
+        for segment in segments {
+            node = match segment {
+                Segment::Static(literal) => node.statics.entry(literal.clone()).or_default(),
+                Segment::Param { name, constraint } => {
+                    param_names.push(name.clone());
+                    node.param_child(constraint)
+                }
+                Segment::CatchAll(name) => {
+                    param_names.push(name.clone());
+                    node.catch_all = Some(Leaf {
+                        route,
+                        param_names,
+                        skipped,
+                    });
+                    return;
+                }
+            };
+        }
+
+        node.leaf = Some(Leaf {
+            route,
+            param_names,
+            skipped,
+        });
+    }
+
+    /// Returns the child for parameters with `constraint`, creating it if needed.
+    fn param_child(&mut self, constraint: &Option<Constraint>) -> &mut Node {
+        let index = match self.params.iter().position(|c| c.constraint == *constraint) {
+            Some(index) => index,
+            None => {
+                // Keep the unconstrained parameter, if any, after all constrained ones.
+                let index = match constraint {
+                    Some(_) => self
+                        .params
+                        .iter()
+                        .position(|c| c.constraint.is_none())
+                        .unwrap_or(self.params.len()),
+                    None => self.params.len(),
+                };
+
+                self.params.insert(
+                    index,
+                    ParamChild {
+                        constraint: constraint.clone(),
+                        node: Node::default(),
+                    },
+                );
+
+                index
+            }
+        };
+
+        &mut self.params[index].node
+    }
+
+    /// Depth-first lookup with backtracking; see the module documentation for the precedence.
+    fn find<'a>(&'a self, segments: &[String], ignore_case: bool) -> Option<Found<'a>> {
+        let Some((first, rest)) = segments.split_first() else {
+            return self.leaf.as_ref().map(Found::new);
+        };
+
+        if let Some(found) = self
+            .statics
+            .get(first)
+            .and_then(|child| child.find(rest, ignore_case))
+        {
+            return Some(found.after(0, None));
+        }
+
+        if ignore_case {
+            // Sorted, so that the outcome does not depend on the order of the map.
+            let mut folded = self
+                .statics
+                .iter()
+                .filter(|(literal, _)| *literal != first && literal.eq_ignore_ascii_case(first))
+                .collect::<Vec<_>>();
+            folded.sort_by_key(|(literal, _)| *literal);
+
+            if let Some(found) = folded
+                .into_iter()
+                .find_map(|(_, child)| child.find(rest, ignore_case))
+            {
+                return Some(found.after(0, None));
+            }
+        }
+
+        // Constrained parameters share a rank, so the rest of the path decides between them.
+        let (constrained, unconstrained): (Vec<_>, Vec<_>) =
+            self.params.iter().partition(|child| child.constraint.is_some());
+        let best = constrained
+            .into_iter()
+            .filter(|child| child.constraint.as_ref().is_some_and(|c| c.is_match(first)))
+            .filter_map(|child| child.node.find(rest, ignore_case))
+            .min_by(|a, b| a.precedence(b));
+        if let Some(found) = best {
+            return Some(found.after(1, Some(first)));
+        }
+
+        if let Some(found) = unconstrained
+            .into_iter()
+            .find_map(|child| child.node.find(rest, ignore_case))
+        {
+            return Some(found.after(2, Some(first)));
+        }
+
+        let leaf = self.catch_all.as_ref()?;
+        let mut found = Found::new(leaf);
+        found.ranks.resize(segments.len(), 3);
+        found.captures.push(segments.join("/"));
+
+        Some(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(tree: &RouteTree<&'static str>, path: &str) -> Option<(&'static str, HashMap<String, String>)> {
+        tree.find(&Url::new(path).unwrap())
+            .map(|m| (*m.value, m.params))
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_static_and_params() {
+        let mut tree = RouteTree::new();
+        tree.insert("/", "root").unwrap();
+        tree.insert("/user-agent", "user-agent").unwrap();
+        tree.insert("/echo/:str", "echo").unwrap();
+        tree.insert("/user/:user_id/profile/:profile_id", "profile").unwrap();
+
+        assert_eq!(find(&tree, "/"), Some(("root", HashMap::new())));
+        assert_eq!(find(&tree, "/user-agent"), Some(("user-agent", HashMap::new())));
+        assert_eq!(find(&tree, "/echo/a%20b"), Some(("echo", params(&[("str", "a b")]))));
+        assert_eq!(
+            find(&tree, "/user/1/profile/2"),
+            Some(("profile", params(&[("user_id", "1"), ("profile_id", "2")])))
+        );
+        assert_eq!(find(&tree, "/echo"), None);
+        assert_eq!(find(&tree, "/echo/a/b"), None);
+        assert_eq!(find(&tree, "/missing"), None);
+    }
+
+    #[test]
+    fn test_find_precedence() {
+        let mut tree = RouteTree::new();
+        tree.insert("/user/*rest", "rest").unwrap();
+        tree.insert("/user/:name", "name").unwrap();
+        tree.insert("/user/:id<uint>", "id").unwrap();
+        tree.insert("/user/me", "me").unwrap();
+
+        assert_eq!(find(&tree, "/user/me").unwrap().0, "me");
+        assert_eq!(find(&tree, "/user/7"), Some(("id", params(&[("id", "7")]))));
+        assert_eq!(find(&tree, "/user/bob"), Some(("name", params(&[("name", "bob")]))));
+        assert_eq!(find(&tree, "/user/bob/x"), Some(("rest", params(&[("rest", "bob/x")]))));
+    }
+
+    #[test]
+    fn test_find_ranks_constrained_params_by_the_rest_of_the_path() {
+        let mut tree = RouteTree::new();
+        tree.insert("/:a<uint>/:b", "uint").unwrap();
+        tree.insert("/:a<int>/x", "int").unwrap();
+
+        assert_eq!(find(&tree, "/1/x"), Some(("int", params(&[("a", "1")]))));
+        assert_eq!(find(&tree, "/1/y"), Some(("uint", params(&[("a", "1"), ("b", "y")]))));
+        assert_eq!(find(&tree, "/-1/x").unwrap().0, "int");
+
+        // Equally specific matches go to the route registered first.
+        let mut tree = RouteTree::new();
+        tree.insert("/:a<int>/:b", "int").unwrap();
+        tree.insert("/:a<uint>/:b", "uint").unwrap();
+        assert_eq!(find(&tree, "/1/y").unwrap().0, "int");
+    }
+
+    #[test]
+    fn test_find_agrees_with_best_match() {
+        let routes = [
+            "/:a<uint>/:b",
+            "/:a<int>/x",
+            "/:a<uint>/:b?/y",
+            "/:a<int>/y",
+            "/:a<alpha>/*rest",
+            "/:a/:b/z",
+            "/s/:b<uint>",
+            "/s/*rest",
+        ];
+        let mut tree = RouteTree::new();
+        for (i, route) in routes.iter().enumerate() {
+            tree.insert(route, i).unwrap();
+        }
+        let patterns = routes.map(|route| RoutePattern::parse(route).unwrap());
+
+        for path in ["/1/x", "/1/y", "/1/2/y", "/-1/y", "/ab/c/z", "/ab/x", "/7/c/z", "/s/1", "/s/a/b"] {
+            let url = Url::new(path).unwrap();
+            let expected = RoutePattern::best_match(&patterns, &url);
+            let found = tree.find(&url).map(|m| (*m.value, m.params));
+
+            assert_eq!(found, expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_find_backtracks() {
+        let mut tree = RouteTree::new();
+        tree.insert("/a/b/c", "static").unwrap();
+        tree.insert("/a/:x/d", "param").unwrap();
+
+        assert_eq!(find(&tree, "/a/b/c").unwrap().0, "static");
+        assert_eq!(find(&tree, "/a/b/d"), Some(("param", params(&[("x", "b")]))));
+    }
+
+    #[test]
+    fn test_find_ignoring_case() {
+        let mut tree = RouteTree::new();
+        tree.insert("/About", "about").unwrap();
+        tree.insert("/about/team", "team").unwrap();
+        tree.insert("/:page", "page").unwrap();
+
+        let find = |path: &str| {
+            let segments = Url::new(path).unwrap().segments();
+            tree.find_segments_ignoring_case(&segments).map(|found| *found.value)
+        };
+
+        assert_eq!(find("/ABOUT"), Some("about"));
+        assert_eq!(find("/ABOUT/Team"), Some("team"));
+        assert_eq!(find("/contact"), Some("page"));
+        assert_eq!(self::find(&tree, "/ABOUT").unwrap().0, "page");
+    }
+
+    #[test]
+    fn test_param_names_per_route() {
+        let mut tree = RouteTree::new();
+        tree.insert("/user/:id/posts", "posts").unwrap();
+        tree.insert("/user/:name/profile", "profile").unwrap();
+
+        assert_eq!(find(&tree, "/user/1/posts"), Some(("posts", params(&[("id", "1")]))));
+        assert_eq!(find(&tree, "/user/bob/profile"), Some(("profile", params(&[("name", "bob")]))));
+    }
+
+    #[test]
+    fn test_optional_segments() {
+        let mut tree = RouteTree::new();
+        tree.insert("/docs/:lang?/intro", "intro").unwrap();
+        tree.insert("/files/*path?", "files").unwrap();
+
+        assert_eq!(find(&tree, "/docs/en/intro"), Some(("intro", params(&[("lang", "en")]))));
+        assert_eq!(find(&tree, "/docs/intro"), Some(("intro", HashMap::new())));
+        assert_eq!(find(&tree, "/files"), Some(("files", HashMap::new())));
+        assert_eq!(find(&tree, "/files/a/b"), Some(("files", params(&[("path", "a/b")]))));
+    }
+
+    #[test]
+    fn test_insert_conflicts() {
+        let mut tree = RouteTree::new();
+        tree.insert("/user/:id", "a").unwrap();
+        tree.insert("/docs/:lang?/intro", "b").unwrap();
+        tree.insert("/files/*path", "c").unwrap();
+
+        assert_eq!(
+            tree.insert("/user/:name", "x"),
+            Err(InsertError::Conflict {
+                pattern: "/user/:name".to_string(),
+                existing: "/user/:id".to_string(),
+            })
+        );
+        assert!(tree.insert("/docs/intro", "x").is_err());
+        assert!(tree.insert("/files/*other", "x").is_err());
+        assert!(tree.insert("/a/:x?/:y?", "x").is_err());
+        assert!(matches!(
+            tree.insert("/bad/*x/y", "x"),
+            Err(InsertError::Pattern(_))
+        ));
+
+        // Constrained and unconstrained parameters do not conflict.
+        assert!(tree.insert("/user/:id<uint>", "d").is_ok());
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn test_failed_insert_leaves_tree_unchanged() {
+        let mut tree = RouteTree::new();
+        tree.insert("/a/b", "a").unwrap();
+
+        assert!(tree.insert("/a/:x?/b?", "x").is_err());
+        assert_eq!(find(&tree, "/a"), None);
+        assert_eq!(find(&tree, "/a/1/b"), None);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![("/a/b", &"a")]);
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+  panic::{self, AssertUnwindSafe},
   sync::{mpsc, Arc, Mutex},
   thread,
 };
@@ -14,7 +15,12 @@ impl Worker {
           let message = receiver.lock().unwrap().recv();
 
           match message {
-              Ok(job) => job(),
+              Ok(job) => {
+                  // A panicking job must not take the worker down with it.
+                  if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                      println!("Worker {id} recovered from a panicking job.");
+                  }
+              }
               Err(_) => {
                   println!("Worker {id} disconnected; shutting down.");
                   break;
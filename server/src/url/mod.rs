@@ -1,3 +1,9 @@
 pub mod parser;
+pub mod path;
+pub mod pattern;
+pub mod percent;
+pub mod query;
 
-pub use parser::Url;
\ No newline at end of file
+pub use parser::Url;
+pub use pattern::RoutePattern;
+pub use query::QueryParams;
\ No newline at end of file
@@ -4,64 +4,81 @@
 //! It provides functionality to parse the URL path and query parameters, as well as to match
 //! the URL path against a specified pattern to extract dynamic segments.
 //!
+//! Percent-encoded characters (RFC 3986, Section 2.1) are decoded in query keys and values,
+//! and in each path segment during matching. Decoding happens per segment, so an encoded
+//! slash (`%2F`) stays part of a single segment instead of splitting it.
+//!
 //! ## Overview
 //!
 //! The `Url` struct encapsulates the following components of a URL:
 //!
 //! - **Path:** The hierarchical part of the URL, indicating the resource's location.
-//! - **Query Parameters:** Optional key-value pairs that provide additional information to the server.
+//! - **Query Parameters:** Optional ordered key-value pairs (see `QueryParams`) that provide additional information to the server.
 //!
 //! Additionally, the module offers a method to match the URL path against a pattern, allowing
-//! for the extraction of dynamic segments (e.g., IDs) from the path.
+//! for the extraction of dynamic segments (e.g., IDs) from the path. Matching operates on the
+//! normalized path (see `Url::normalized_path`), so `.`/`..` segments and repeated slashes are
+//! resolved before any segment is compared.
 //!
 //! ## Usage
 //!
 //! To create a `Url` instance, use the `Url::new` method by passing the URL string from the HTTP request.
-//! This method parses the URL into its path and query parameters.
+//! This method parses the URL into its path and query parameters, and fails if the URL contains
+//! an invalid percent-encoding.
 //!
 //! ```rust
 //! use server::url::parser::Url;
 //!
-//! let url = Url::new("/search?q=rust&sort=asc");
+//! let url = Url::new("/search?q=rust%20lang&sort=asc").unwrap();
+//! let query = url.query.unwrap();
 //! assert_eq!(url.path, "/search");
-//! assert_eq!(url.query, Some({
-//!     let mut map = std::collections::HashMap::new();
-//!     map.insert("q".to_string(), "rust".to_string());
-//!     map.insert("sort".to_string(), "asc".to_string());
-//!     map
-//! }));
+//! assert_eq!(query.get("q"), Some("rust lang"));
+//! assert_eq!(query.get("sort"), Some("asc"));
+//!
+//! assert!(Url::new("/search?q=100%").is_err());
 //! ```
 #![allow(dead_code)]
 
 use std::collections::HashMap;
 
+use super::{
+    path::normalize_path,
+    pattern::RoutePattern,
+    percent::{percent_decode, PercentDecodeError},
+    query::QueryParams,
+};
+
 /// Represents a parsed URL from an HTTP request.
 ///
 /// The `Url` struct contains the path and optional query parameters extracted from the URL.
 #[derive(Debug, PartialEq)]
 pub struct Url {
     /// The path component of the URL (e.g., `/home`, `/user/123`).
+    ///
+    /// The path is kept percent-encoded, exactly as received; segments are decoded by `match_path`.
     pub path: String,
 
-    /// The query parameters of the URL as key-value pairs, if present.
+    /// The percent-decoded query parameters of the URL, in order, if present.
     ///
-    /// For example, in `/search?q=rust&sort=asc`, the query would be:
+    /// For example, in `/search?q=rust&tag=a&tag=b`, the query would be:
     ///
     /// ```rust
-    /// use std::collections::HashMap;
+    /// use server::url::query::QueryParams;
     ///
-    /// let mut query = HashMap::new();
-    /// query.insert("q".to_string(), "rust".to_string());
-    /// query.insert("sort".to_string(), "asc".to_string());
+    /// let mut query = QueryParams::new();
+    /// query.append("q", Some("rust"));
+    /// query.append("tag", Some("a"));
+    /// query.append("tag", Some("b"));
     /// ```
-    pub query: Option<HashMap<String, String>>,
+    pub query: Option<QueryParams>,
 }
 
 impl Url {
     /// Constructs a new `Url` by parsing the given URL string.
     ///
     /// This method splits the URL into its path and query components. The query string is further
-    /// parsed into key-value pairs and stored in a `HashMap`.
+    /// parsed into percent-decoded, ordered key-value pairs and stored in `QueryParams`. The path is
+    /// validated but kept encoded.
     ///
     /// # Parameters
     ///
@@ -69,75 +86,82 @@ impl Url {
     ///
     /// # Returns
     ///
-    /// A `Url` instance containing the parsed path and query parameters.
+    /// - `Ok(Url)`: A `Url` instance containing the parsed path and query parameters.
+    /// - `Err(PercentDecodeError)`: If the path or query contains an invalid percent-encoding.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use server::url::parser::Url;
-    /// use std::collections::HashMap;
+    /// use server::url::{parser::Url, query::QueryParams};
     ///
-    /// let url = Url::new("/search?q=rust&sort=asc");
+    /// let url = Url::new("/search?q=rust&sort=asc").unwrap();
     /// assert_eq!(url.path, "/search");
-    /// assert_eq!(url.query, Some({
-    ///     let mut map = HashMap::new();
-    ///     map.insert("q".to_string(), "rust".to_string());
-    ///     map.insert("sort".to_string(), "asc".to_string());
-    ///     map
-    /// }));
+    /// assert_eq!(url.query, Some(QueryParams::from_iter([("q", "rust"), ("sort", "asc")])));
     /// ```
-    pub fn new(url: &str) -> Url {
+    pub fn new(url: &str) -> Result<Url, PercentDecodeError> {
         let parts: Vec<&str> = url.splitn(2, '?').collect();
         let path = parts[0].to_string();
         let query = if parts.len() > 1 {
-            Some(Self::parse_query(parts[1]))
+            Some(QueryParams::parse(parts[1])?)
         } else {
             None
         };
 
-        Url {
+        for segment in path.split('/') {
+            percent_decode(segment)?;
+        }
+
+        Ok(Url {
             path,
             query,
-        }
+        })
     }
 
-    /// Parses the query string into a `HashMap` of key-value pairs.
+    /// Returns the path with dot segments removed and repeated slashes collapsed.
     ///
-    /// This private helper method splits the query string by `&` to separate parameters,
-    /// and then splits each parameter by `=` to obtain keys and values.
+    /// The result is still percent-encoded; see `path::normalize_path` for the exact rules.
     ///
-    /// # Parameters
-    ///
-    /// - `query`: A string slice representing the query portion of the URL (e.g., `"q=rust&sort=asc"`).
+    /// # Examples
     ///
-    /// # Returns
+    /// ```rust
+    /// use server::url::parser::Url;
     ///
-    /// A `HashMap<String, String>` containing the parsed query parameters.
+    /// let url = Url::new("//files/./docs/../img//logo.png").unwrap();
+    /// assert_eq!(url.path, "//files/./docs/../img//logo.png");
+    /// assert_eq!(url.normalized_path(), "/files/img/logo.png");
+    /// ```
+    pub fn normalized_path(&self) -> String {
+        normalize_path(&self.path)
+    }
+
+    /// Returns the percent-decoded segments of the normalized path, skipping empty ones.
     ///
     /// # Examples
     ///
-    /// This method is private and cannot be called outside the `Url` struct.
-    fn parse_query(query: &str) -> HashMap<String, String> {
-        query
-            .split('&')
-            .filter_map(|s| {
-                let mut pair = s.splitn(2, '=');
-                let key = pair.next()?;
-                let value = pair.next()?;
-
-                if key.len() > 0 {
-                    Some((key.to_string(), value.to_string()))
-                } else {
-                    None
-                }
-            })
+    /// ```rust
+    /// use server::url::parser::Url;
+    ///
+    /// let url = Url::new("/files//docs%2Fguide.md/../intro%20one.md").unwrap();
+    /// assert_eq!(url.segments(), vec!["files", "intro one.md"]);
+    /// ```
+    pub fn segments(&self) -> Vec<String> {
+        // `Url::new` has already validated every segment, so decoding cannot fail.
+        self.normalized_path()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| percent_decode(s).unwrap_or_else(|_| s.to_string()))
             .collect()
     }
 
     /// Matches the URL path against a given pattern and extracts dynamic segments.
     ///
     /// The pattern can contain dynamic segments prefixed with `:`, which will capture the corresponding
-    /// part of the URL path as parameters.
+    /// part of the URL path as parameters. Each URL segment is percent-decoded before it is compared
+    /// or captured, so `%2F` yields a `/` inside a single parameter.
+    ///
+    /// The full `RoutePattern` syntax is supported, including catch-alls (`*path`), optional
+    /// segments (`:lang?`) and constraints (`:id<\d+>`). The pattern is compiled on every call;
+    /// use `RoutePattern` directly to match the same pattern repeatedly.
     ///
     /// # Parameters
     ///
@@ -146,7 +170,7 @@ impl Url {
     /// # Returns
     ///
     /// - `Some(HashMap<String, String>)`: A map of parameter names to their extracted values if the path matches the pattern.
-    /// - `None`: If the path does not match the pattern.
+    /// - `None`: If the path does not match the pattern, or the pattern is invalid.
     ///
     /// # Examples
     ///
@@ -154,7 +178,7 @@ impl Url {
     /// use server::url::parser::Url;
     /// use std::collections::HashMap;
     ///
-    /// let url = Url::new("/user/123/profile/456");
+    /// let url = Url::new("/user/123/profile/456").unwrap();
     /// let pattern = "/user/:user_id/profile/:profile_id";
     ///
     /// let params = url.match_path(pattern).unwrap();
@@ -165,38 +189,31 @@ impl Url {
     /// ```rust
     /// use server::url::parser::Url;
     ///
-    /// let url = Url::new("/about");
+    /// let url = Url::new("/about").unwrap();
     /// let pattern = "/contact";
     ///
     /// assert_eq!(url.match_path(pattern), None);
     /// ```
+    ///
+    /// ```rust
+    /// use server::url::parser::Url;
+    ///
+    /// let url = Url::new("/files/docs%2Fguide.md").unwrap();
+    ///
+    /// let params = url.match_path("/files/:file_name").unwrap();
+    /// assert_eq!(params.get("file_name").unwrap(), "docs/guide.md");
+    /// ```
+    ///
+    /// ```rust
+    /// use server::url::parser::Url;
+    ///
+    /// let url = Url::new("/files/docs/guide/intro.md").unwrap();
+    ///
+    /// let params = url.match_path("/files/*file_name").unwrap();
+    /// assert_eq!(params.get("file_name").unwrap(), "docs/guide/intro.md");
+    /// ```
     pub fn match_path(&self, pattern: &str) -> Option<HashMap<String, String>> {
-        let url_segments = self
-            .path
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<&str>>();
-        let pattern_segments = pattern
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<&str>>();
-
-        if url_segments.len() != pattern_segments.len() {
-            return None;
-        }
-
-        let mut params = HashMap::new();
-
-        for (url_seg, pattern_seg) in url_segments.iter().zip(pattern_segments.iter()) {
-            if pattern_seg.starts_with(':') {
-                let param_name = &pattern_seg[1..];
-                params.insert(param_name.to_string(), url_seg.to_string());
-            } else if url_seg != pattern_seg {
-                return None;
-            }
-        }
-
-        Some(params)
+        RoutePattern::parse(pattern).ok()?.matches(self)
     }
 }
 
@@ -207,80 +224,78 @@ mod tests {
 
     #[test]
     fn test_url_new_without_query() {
-        let url = Url::new("/home");
+        let url = Url::new("/home").unwrap();
         assert_eq!(url.path, "/home");
         assert!(url.query.is_none());
     }
 
     #[test]
     fn test_url_new_with_multiple_queries() {
-        let url = Url::new("/search?q=rust&sort=asc&limit=10");
+        let url = Url::new("/search?q=rust&sort=asc&limit=10").unwrap();
         assert_eq!(url.path, "/search");
-        let mut expected_query = HashMap::new();
-        expected_query.insert("q".to_string(), "rust".to_string());
-        expected_query.insert("sort".to_string(), "asc".to_string());
-        expected_query.insert("limit".to_string(), "10".to_string());
+        let expected_query = QueryParams::from_iter([("q", "rust"), ("sort", "asc"), ("limit", "10")]);
         assert_eq!(url.query, Some(expected_query));
     }
 
     #[test]
     fn test_url_new_with_empty_query() {
-        let url = Url::new("/path?");
+        let url = Url::new("/path?").unwrap();
         assert_eq!(url.path, "/path");
-        let expected_query = Some(HashMap::new());
+        let expected_query = Some(QueryParams::new());
         assert_eq!(url.query, expected_query);
     }
 
     #[test]
-    fn test_url_new_with_invalid_query() {
-        let url = Url::new("/path?key1=value1&key2");
+    fn test_url_new_with_valueless_query_key() {
+        let url = Url::new("/path?key1=value1&key2").unwrap();
         assert_eq!(url.path, "/path");
-        let mut expected_query = HashMap::new();
-        expected_query.insert("key1".to_string(), "value1".to_string());
+        let mut expected_query = QueryParams::new();
+        expected_query.append("key1", Some("value1"));
+        expected_query.append("key2", None);
         assert_eq!(url.query, Some(expected_query));
     }
 
+    #[test]
+    fn test_url_new_with_repeated_query_keys() {
+        let url = Url::new("/path?tag=a&tag=b").unwrap();
+        let query = url.query.unwrap();
+        assert_eq!(query.get_all("tag"), vec!["a", "b"]);
+    }
+
     #[test]
     fn test_parse_query_empty() {
         let query = "";
-        let parsed = Url::parse_query(query);
-        let expected: HashMap<String, String> = HashMap::new();
+        let parsed = QueryParams::parse(query).unwrap();
+        let expected = QueryParams::new();
         assert_eq!(parsed, expected);
     }
 
     #[test]
     fn test_parse_query_multiple_params() {
         let query = "key1=value1&key2=value2&key3=value3";
-        let mut expected = HashMap::new();
-        expected.insert("key1".to_string(), "value1".to_string());
-        expected.insert("key2".to_string(), "value2".to_string());
-        expected.insert("key3".to_string(), "value3".to_string());
-        let parsed = Url::parse_query(query);
+        let expected = QueryParams::from_iter([("key1", "value1"), ("key2", "value2"), ("key3", "value3")]);
+        let parsed = QueryParams::parse(query).unwrap();
         assert_eq!(parsed, expected);
     }
 
     #[test]
     fn test_parse_query_with_missing_value() {
         let query = "key1=value1&key2=&key3=value3";
-        let mut expected = HashMap::new();
-        expected.insert("key1".to_string(), "value1".to_string());
-        expected.insert("key2".to_string(), "".to_string());
-        expected.insert("key3".to_string(), "value3".to_string());
-        let parsed = Url::parse_query(query);
+        let expected = QueryParams::from_iter([("key1", "value1"), ("key2", ""), ("key3", "value3")]);
+        let parsed = QueryParams::parse(query).unwrap();
         assert_eq!(parsed, expected);
     }
 
     #[test]
     fn test_parse_query_with_missing_key() {
         let query = "=value1&key2=value2";
-        let mut expected = HashMap::new();
-        expected.insert("key2".to_string(), "value2".to_string());
-        assert_eq!(Url::parse_query(query), expected);
+        let expected = QueryParams::from_iter([("key2", "value2")]);
+        assert_eq!(QueryParams::parse(query).unwrap(), expected);
     }
 
     #[test]
     fn test_match_path_exact_match() {
-        let url = Url::new("/home");
+        let url = Url::new("/home").unwrap();
         let pattern = "/home";
         let result = url.match_path(pattern);
         assert_eq!(result, Some(HashMap::new()));
@@ -288,7 +303,7 @@ mod tests {
 
     #[test]
     fn test_match_path_with_multiple_parameters() {
-        let url = Url::new("/user/123/profile/456");
+        let url = Url::new("/user/123/profile/456").unwrap();
         let pattern = "/user/:user_id/profile/:profile_id";
         let mut expected = HashMap::new();
         expected.insert("user_id".to_string(), "123".to_string());
@@ -299,7 +314,7 @@ mod tests {
 
     #[test]
     fn test_match_path_non_matching_pattern() {
-        let url = Url::new("/user/123");
+        let url = Url::new("/user/123").unwrap();
         let pattern = "/admin/:id";
         let result = url.match_path(pattern);
         assert_eq!(result, None);
@@ -307,7 +322,7 @@ mod tests {
 
     #[test]
     fn test_match_path_different_segment_count() {
-        let url = Url::new("/user/123");
+        let url = Url::new("/user/123").unwrap();
         let pattern = "/user/:id/details";
         let result = url.match_path(pattern);
         assert_eq!(result, None);
@@ -315,9 +330,65 @@ mod tests {
 
     #[test]
     fn test_match_path_with_no_parameters() {
-        let url = Url::new("/about");
+        let url = Url::new("/about").unwrap();
         let pattern = "/about";
         let result = url.match_path(pattern);
         assert_eq!(result, Some(HashMap::new()));
     }
+
+    #[test]
+    fn test_url_new_decodes_query() {
+        let url = Url::new("/search?q=a%26b&na%20me=x+y%3Dz").unwrap();
+        let expected_query = QueryParams::from_iter([("q", "a&b"), ("na me", "x y=z")]);
+        assert_eq!(url.query, Some(expected_query));
+    }
+
+    #[test]
+    fn test_url_new_keeps_path_encoded() {
+        let url = Url::new("/echo/hello%20world").unwrap();
+        assert_eq!(url.path, "/echo/hello%20world");
+    }
+
+    #[test]
+    fn test_url_new_with_invalid_escapes() {
+        assert!(Url::new("/echo/100%").is_err());
+        assert!(Url::new("/echo/%zz").is_err());
+        assert!(Url::new("/echo/%C3%28").is_err());
+        assert!(Url::new("/path?q=%2").is_err());
+        assert!(Url::new("/path?%g0=value").is_err());
+    }
+
+    #[test]
+    fn test_match_path_decodes_segments() {
+        let url = Url::new("/echo/hello%20world").unwrap();
+        let mut expected = HashMap::new();
+        expected.insert("str".to_string(), "hello world".to_string());
+        assert_eq!(url.match_path("/echo/:str"), Some(expected));
+    }
+
+    #[test]
+    fn test_match_path_encoded_slash_stays_in_segment() {
+        let url = Url::new("/files/a%2Fb").unwrap();
+        let mut expected = HashMap::new();
+        expected.insert("file_name".to_string(), "a/b".to_string());
+        assert_eq!(url.match_path("/files/:file_name"), Some(expected));
+        assert_eq!(url.match_path("/files/a/b"), None);
+    }
+
+    #[test]
+    fn test_match_path_decodes_literal_segments() {
+        let url = Url::new("/caf%C3%A9").unwrap();
+        assert_eq!(url.match_path("/café"), Some(HashMap::new()));
+    }
+
+    #[test]
+    fn test_match_path_uses_normalized_path() {
+        let url = Url::new("//user/./42/../7//").unwrap();
+        let mut expected = HashMap::new();
+        expected.insert("id".to_string(), "7".to_string());
+        assert_eq!(url.match_path("/user/:id"), Some(expected));
+
+        let url = Url::new("/files/%2e%2e/secret").unwrap();
+        assert_eq!(url.match_path("/files/:file_name"), None);
+    }
 }
\ No newline at end of file
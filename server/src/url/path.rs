@@ -0,0 +1,293 @@
+//! # Path Module
+//!
+//! This module provides normalization of URL paths and a traversal-safe way to map them onto the
+//! file system.
+//!
+//! - `remove_dot_segments` implements the algorithm from RFC 3986, Section 5.2.4, which resolves
+//!   `.` and `..` segments.
+//! - `normalize_path` additionally collapses repeated slashes.
+//! - `safe_join` resolves a path under a root directory and refuses anything that would escape
+//!   it, either lexically (`..`) or through symbolic links.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::url::path::{normalize_path, remove_dot_segments};
+//!
+//! assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+//! assert_eq!(normalize_path("//files/./docs//../img/logo.png"), "/files/img/logo.png");
+//! ```
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+/// Removes `.` and `..` segments from a path, as described in RFC 3986, Section 5.2.4.
+///
+/// Percent-encoded dots (`%2E`) are treated like literal dots. A `..` that would go above the
+/// root is dropped, and a path ending in a dot segment keeps its trailing slash.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::url::path::remove_dot_segments;
+///
+/// assert_eq!(remove_dot_segments("/a/b/.."), "/a/");
+/// assert_eq!(remove_dot_segments("/../etc/passwd"), "/etc/passwd");
+/// assert_eq!(remove_dot_segments("/a/%2E%2E/b"), "/b");
+/// ```
+pub fn remove_dot_segments(path: &str) -> String {
+    resolve_segments(path, false)
+}
+
+/// Normalizes a URL path by collapsing repeated slashes and removing dot segments.
+///
+/// A trailing slash is preserved, and an empty path becomes `/`.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::url::path::normalize_path;
+///
+/// assert_eq!(normalize_path("//echo//abc"), "/echo/abc");
+/// assert_eq!(normalize_path("/echo/abc/"), "/echo/abc/");
+/// assert_eq!(normalize_path(""), "/");
+/// ```
+pub fn normalize_path(path: &str) -> String {
+    let normalized = resolve_segments(path, true);
+
+    if normalized.is_empty() {
+        "/".to_string()
+    } else {
+        normalized
+    }
+}
+
+/// Resolves a decoded, `/`-separated `path` under the directory `root`.
+///
+/// The path is interpreted relative to `root` regardless of a leading slash. Resolution fails if:
+///
+/// - a segment contains a backslash or NUL byte (`ErrorKind::InvalidInput`),
+/// - `..` segments would climb above `root` (`ErrorKind::PermissionDenied`),
+/// - the resolved location, or its closest existing ancestor, lies outside `root` once symbolic
+///   links are followed (`ErrorKind::PermissionDenied`),
+/// - `root` itself cannot be resolved (the underlying I/O error).
+///
+/// The target does not need to exist, so the result can be used both for reading and for
+/// creating files. The returned path is built from the canonical form of `root`.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::url::path::safe_join;
+/// use std::{env, io::ErrorKind};
+///
+/// let root = env::temp_dir();
+///
+/// let path = safe_join(&root, "docs/./guide.md").unwrap();
+/// assert!(path.ends_with("docs/guide.md"));
+///
+/// let error = safe_join(&root, "../etc/passwd").unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+/// ```
+pub fn safe_join(root: &Path, path: &str) -> Result<PathBuf> {
+    let root = root.canonicalize()?;
+    let mut joined = root.clone();
+    let mut depth = 0usize;
+
+    for segment in path.split('/') {
+        if segment.contains('\\') || segment.contains('\0') {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid path segment {:?}", segment),
+            ));
+        }
+
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if depth == 0 {
+                    return Err(escape_error(path));
+                }
+
+                joined.pop();
+                depth -= 1;
+            }
+            _ => {
+                joined.push(segment);
+                depth += 1;
+            }
+        }
+    }
+
+    // Follow symlinks on the longest existing prefix of the path.
+    let existing = joined
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(&root);
+
+    if !existing.canonicalize()?.starts_with(&root) {
+        return Err(escape_error(path));
+    }
+
+    Ok(joined)
+}
+
+fn escape_error(path: &str) -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        format!("Path {:?} escapes the root directory", path),
+    )
+}
+
+/// Shared implementation of `remove_dot_segments` and `normalize_path`.
+fn resolve_segments(path: &str, collapse_slashes: bool) -> String {
+    let absolute = path.starts_with('/');
+    let input = if absolute { &path[1..] } else { path };
+    let segments = input.split('/').collect::<Vec<&str>>();
+    let mut output: Vec<&str> = Vec::with_capacity(segments.len());
+
+    for (i, segment) in segments.iter().enumerate() {
+        let is_last = i == segments.len() - 1;
+
+        match dot_segment(segment) {
+            Some(1) => {}
+            Some(_) => {
+                output.pop();
+            }
+            // Empty segments come from repeated slashes; only a trailing one is kept.
+            None if segment.is_empty() && collapse_slashes && !is_last => {}
+            None => output.push(segment),
+        }
+
+        if is_last && dot_segment(segment).is_some() {
+            output.push("");
+        }
+    }
+
+    let joined = output.join("/");
+
+    if absolute {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
+/// Returns `Some(1)` for `.` and `Some(2)` for `..`, accepting percent-encoded dots.
+fn dot_segment(segment: &str) -> Option<usize> {
+    let mut rest = segment;
+    let mut dots = 0;
+
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            rest = r;
+        } else if rest.len() >= 3 && rest.as_bytes()[..3].eq_ignore_ascii_case(b"%2e") {
+            rest = &rest[3..];
+        } else {
+            return None;
+        }
+
+        dots += 1;
+    }
+
+    match dots {
+        1 | 2 => Some(dots),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_remove_dot_segments_rfc_examples() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("mid/content=5/../6"), "mid/6");
+        assert_eq!(remove_dot_segments("/a/b/c/."), "/a/b/c/");
+        assert_eq!(remove_dot_segments("/a/b/c/.."), "/a/b/");
+        assert_eq!(remove_dot_segments("/../../g"), "/g");
+        assert_eq!(remove_dot_segments("/a//b"), "/a//b");
+        assert_eq!(remove_dot_segments("/"), "/");
+    }
+
+    #[test]
+    fn test_remove_dot_segments_encoded_dots() {
+        assert_eq!(remove_dot_segments("/a/%2e%2E/b"), "/b");
+        assert_eq!(remove_dot_segments("/a/.%2e/b"), "/b");
+        assert_eq!(remove_dot_segments("/a/%2e/b"), "/a/b");
+        assert_eq!(remove_dot_segments("/a/.../b"), "/a/.../b");
+        assert_eq!(remove_dot_segments("/a/%2e%2e%2e/b"), "/a/%2e%2e%2e/b");
+        assert_eq!(remove_dot_segments("/a/%2é/b"), "/a/%2é/b");
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/echo/abc"), "/echo/abc");
+        assert_eq!(normalize_path("//echo//abc"), "/echo/abc");
+        assert_eq!(normalize_path("/echo/abc//"), "/echo/abc/");
+        assert_eq!(normalize_path("/a//../b"), "/b");
+        assert_eq!(normalize_path("/a/b/./"), "/a/b/");
+        assert_eq!(normalize_path("/.."), "/");
+        assert_eq!(normalize_path("//"), "/");
+        assert_eq!(normalize_path(""), "/");
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!("server-path-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/guide.md"), "guide").unwrap();
+
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_safe_join_inside_root() {
+        let root = temp_root("inside");
+
+        assert_eq!(safe_join(&root, "docs/guide.md").unwrap(), root.join("docs/guide.md"));
+        assert_eq!(safe_join(&root, "/docs//./guide.md").unwrap(), root.join("docs/guide.md"));
+        assert_eq!(safe_join(&root, "docs/../new.txt").unwrap(), root.join("new.txt"));
+        assert_eq!(safe_join(&root, "").unwrap(), root);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_safe_join_refuses_traversal() {
+        let root = temp_root("traversal");
+
+        for path in ["..", "../etc/passwd", "docs/../../x", "/../x"] {
+            let error = safe_join(&root, path).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::PermissionDenied, "{path}");
+        }
+
+        let error = safe_join(&root, "docs\\..\\..\\x").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_safe_join_refuses_symlink_escape() {
+        let root = temp_root("symlink");
+        let outside = temp_root("symlink-outside");
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        std::os::unix::fs::symlink(root.join("docs"), root.join("inner")).unwrap();
+
+        let error = safe_join(&root, "link/docs/guide.md").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+        let error = safe_join(&root, "link/new.txt").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+
+        assert!(safe_join(&root, "inner/guide.md").is_ok());
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+}
@@ -0,0 +1,590 @@
+//! # Route Pattern Module
+//!
+//! This module defines the `RoutePattern` struct, a compiled route pattern that URL paths can be
+//! matched against to extract named parameters.
+//!
+//! ## Syntax
+//!
+//! Patterns are made of `/`-separated segments:
+//!
+//! - **Static:** `users` matches exactly that (percent-decoded) segment.
+//! - **Parameter:** `:id` captures any single segment under the name `id`.
+//! - **Constrained parameter:** `:id<\d+>` only captures segments fully matching the regular
+//!   expression. Named constraints are also available: `int`, `uint`, `alpha`, `alnum`, `slug`
+//!   and `uuid` (e.g. `:id<uint>`). Constraints may not contain `/`.
+//! - **Catch-all:** `*path` captures one or more remaining segments, joined by `/`. It must be the
+//!   last segment of the pattern.
+//! - **Optional:** any of the above followed by `?` may also be skipped, e.g. `/docs/:lang?/intro`
+//!   or `/files/*path?`.
+//!
+//! ## Precedence
+//!
+//! When several patterns match the same path, the most specific one wins. Segments are compared
+//! from left to right, and at the first position where the patterns differ, the one matching the
+//! URL segment with the more specific kind wins: static, then constrained parameter, then
+//! parameter, then catch-all. If that does not decide, a match that skipped fewer optional
+//! segments wins, and finally the pattern listed first.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::url::{parser::Url, pattern::RoutePattern};
+//!
+//! let pattern = RoutePattern::parse("/files/*path").unwrap();
+//! let url = Url::new("/files/docs/guide/intro.md").unwrap();
+//!
+//! let params = pattern.matches(&url).unwrap();
+//! assert_eq!(params.get("path").unwrap(), "docs/guide/intro.md");
+//!
+//! let pattern = RoutePattern::parse("/user/:id<\\d+>").unwrap();
+//! assert!(pattern.matches(&Url::new("/user/42").unwrap()).is_some());
+//! assert!(pattern.matches(&Url::new("/user/bob").unwrap()).is_none());
+//! ```
+
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use regex::Regex;
+
+use super::parser::Url;
+
+/// Error returned when a route pattern cannot be parsed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PatternError {
+    /// The pattern that failed to parse.
+    pub pattern: String,
+
+    /// Why parsing failed.
+    pub reason: String,
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid route pattern {:?}: {}", self.pattern, self.reason)
+    }
+}
+
+impl Error for PatternError {}
+
+/// A regular expression that a parameter segment must fully match.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    source: String,
+    regex: Regex,
+}
+
+impl Constraint {
+    /// Compiles a constraint from a named type (e.g. `uint`) or a regular expression.
+    fn parse(source: &str) -> Result<Constraint, String> {
+        let expression = match source {
+            "int" => r"-?[0-9]+",
+            "uint" => r"[0-9]+",
+            "alpha" => r"[A-Za-z]+",
+            "alnum" => r"[A-Za-z0-9]+",
+            "slug" => r"[a-z0-9]+(?:-[a-z0-9]+)*",
+            "uuid" => r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}",
+            _ => source,
+        };
+
+        let regex = Regex::new(&format!("^(?:{})$", expression))
+            .map_err(|e| format!("invalid constraint <{}>: {}", source, e))?;
+
+        Ok(Constraint {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    /// Returns the constraint as written in the pattern, without the angle brackets.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns `true` if `value` satisfies the constraint.
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Constraint {}
+
+/// A single segment of a `RoutePattern`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Segment {
+    /// A literal segment.
+    Static(String),
+
+    /// A named capture of one segment, optionally constrained.
+    Param {
+        name: String,
+        constraint: Option<Constraint>,
+    },
+
+    /// A named capture of all remaining segments.
+    CatchAll(String),
+}
+
+impl Segment {
+    /// Precedence rank of the segment kind; lower is more specific.
+    pub(crate) fn rank(&self) -> u8 {
+        match self {
+            Segment::Static(_) => 0,
+            Segment::Param {
+                constraint: Some(_),
+                ..
+            } => 1,
+            Segment::Param { .. } => 2,
+            Segment::CatchAll(_) => 3,
+        }
+    }
+}
+
+/// A parsed pattern segment together with whether it may be skipped.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PatternSegment {
+    pub segment: Segment,
+    pub optional: bool,
+}
+
+/// A compiled route pattern.
+///
+/// See the module documentation for the syntax and precedence rules.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RoutePattern {
+    source: String,
+    segments: Vec<PatternSegment>,
+}
+
+/// The outcome of matching a pattern, used to rank competing matches.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Specificity {
+    /// Rank of the pattern segment that matched each URL segment, in order.
+    ranks: Vec<u8>,
+
+    /// Number of optional segments that were skipped.
+    skipped: usize,
+}
+
+impl RoutePattern {
+    /// Parses and compiles a route pattern.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(RoutePattern)`: The compiled pattern.
+    /// - `Err(PatternError)`: If a segment is malformed, a parameter name is empty or repeated,
+    ///   a constraint is not a valid regular expression, or a catch-all is not the last segment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::url::pattern::RoutePattern;
+    ///
+    /// assert!(RoutePattern::parse("/user/:id<uint>/posts/:slug?").is_ok());
+    /// assert!(RoutePattern::parse("/files/*path/edit").is_err());
+    /// assert!(RoutePattern::parse("/a/:id/b/:id").is_err());
+    /// ```
+    pub fn parse(pattern: &str) -> Result<RoutePattern, PatternError> {
+        let error = |reason: String| PatternError {
+            pattern: pattern.to_string(),
+            reason,
+        };
+
+        let raw_segments = pattern
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<&str>>();
+        let mut segments = Vec::with_capacity(raw_segments.len());
+        let mut names: Vec<&str> = Vec::new();
+
+        for (i, raw) in raw_segments.iter().enumerate() {
+            let (body, optional) = Self::split_optional(raw);
+
+            let segment = if let Some(rest) = body.strip_prefix(':') {
+                let (name, constraint) = match rest.split_once('<') {
+                    Some((name, constraint)) => {
+                        let constraint = constraint
+                            .strip_suffix('>')
+                            .ok_or_else(|| error(format!("unterminated constraint in {:?}", raw)))?;
+
+                        (name, Some(Constraint::parse(constraint).map_err(&error)?))
+                    }
+                    None => (rest, None),
+                };
+
+                names.push(name);
+
+                Segment::Param {
+                    name: name.to_string(),
+                    constraint,
+                }
+            } else if let Some(name) = body.strip_prefix('*') {
+                if i != raw_segments.len() - 1 {
+                    return Err(error("a catch-all must be the last segment".to_string()));
+                }
+
+                names.push(name);
+
+                Segment::CatchAll(name.to_string())
+            } else {
+                Segment::Static(body.to_string())
+            };
+
+            segments.push(PatternSegment { segment, optional });
+        }
+
+        for (i, name) in names.iter().enumerate() {
+            if name.is_empty() {
+                return Err(error("parameter names cannot be empty".to_string()));
+            }
+
+            if names[..i].contains(name) {
+                return Err(error(format!("duplicate parameter name {:?}", name)));
+            }
+        }
+
+        Ok(RoutePattern {
+            source: pattern.to_string(),
+            segments,
+        })
+    }
+
+    /// Splits a trailing `?` (optional marker) from a raw segment.
+    ///
+    /// For constrained parameters, the marker must follow the closing `>`, so a `?`
+    /// inside the constraint is left alone.
+    fn split_optional(raw: &str) -> (&str, bool) {
+        match raw.strip_suffix('?') {
+            Some(body) if !body.contains('<') || body.ends_with('>') => (body, true),
+            _ => (raw, false),
+        }
+    }
+
+    /// Returns the pattern as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the parsed segments of the pattern.
+    pub fn segments(&self) -> &[PatternSegment] {
+        &self.segments
+    }
+
+    /// Matches the normalized, percent-decoded path of `url` against the pattern.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(HashMap<String, String>)`: The captured parameters if the path matches.
+    ///   Skipped optional parameters are not present in the map.
+    /// - `None`: If the path does not match.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::url::{parser::Url, pattern::RoutePattern};
+    ///
+    /// let pattern = RoutePattern::parse("/docs/:lang?/intro").unwrap();
+    ///
+    /// let params = pattern.matches(&Url::new("/docs/en/intro").unwrap()).unwrap();
+    /// assert_eq!(params.get("lang").unwrap(), "en");
+    ///
+    /// let params = pattern.matches(&Url::new("/docs/intro").unwrap()).unwrap();
+    /// assert!(params.get("lang").is_none());
+    /// ```
+    pub fn matches(&self, url: &Url) -> Option<HashMap<String, String>> {
+        self.match_segments(&url.segments()).map(|(params, _)| params)
+    }
+
+    /// Finds the most specific of `patterns` matching `url`, according to the precedence rules.
+    ///
+    /// # Returns
+    ///
+    /// - `Some((index, params))`: The index of the winning pattern and its captured parameters.
+    /// - `None`: If no pattern matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::url::{parser::Url, pattern::RoutePattern};
+    ///
+    /// let patterns = [
+    ///     RoutePattern::parse("/files/*path").unwrap(),
+    ///     RoutePattern::parse("/files/:name").unwrap(),
+    ///     RoutePattern::parse("/files/index.html").unwrap(),
+    /// ];
+    ///
+    /// let url = Url::new("/files/index.html").unwrap();
+    /// assert_eq!(RoutePattern::best_match(&patterns, &url).unwrap().0, 2);
+    ///
+    /// let url = Url::new("/files/about.html").unwrap();
+    /// assert_eq!(RoutePattern::best_match(&patterns, &url).unwrap().0, 1);
+    ///
+    /// let url = Url::new("/files/img/logo.png").unwrap();
+    /// assert_eq!(RoutePattern::best_match(&patterns, &url).unwrap().0, 0);
+    /// ```
+    pub fn best_match<'a, I>(patterns: I, url: &Url) -> Option<(usize, HashMap<String, String>)>
+    where
+        I: IntoIterator<Item = &'a RoutePattern>,
+    {
+        let segments = url.segments();
+        let mut best: Option<(usize, HashMap<String, String>, Specificity)> = None;
+
+        for (i, pattern) in patterns.into_iter().enumerate() {
+            if let Some((params, specificity)) = pattern.match_segments(&segments) {
+                if best.as_ref().map_or(true, |(_, _, b)| specificity < *b) {
+                    best = Some((i, params, specificity));
+                }
+            }
+        }
+
+        best.map(|(i, params, _)| (i, params))
+    }
+
+    fn match_segments(&self, url_segments: &[String]) -> Option<(HashMap<String, String>, Specificity)> {
+        let mut params = HashMap::new();
+        let mut specificity = Specificity {
+            ranks: Vec::with_capacity(url_segments.len()),
+            skipped: 0,
+        };
+
+        if self.match_from(0, url_segments, &mut params, &mut specificity) {
+            Some((params, specificity))
+        } else {
+            None
+        }
+    }
+
+    /// Backtracking matcher: tries to match `self.segments[index..]` against `url_segments`.
+    ///
+    /// Optional segments are first taken, then skipped, so the first successful
+    /// match is also the most specific one for this pattern.
+    fn match_from(
+        &self,
+        index: usize,
+        url_segments: &[String],
+        params: &mut HashMap<String, String>,
+        specificity: &mut Specificity,
+    ) -> bool {
+        let Some(pattern_segment) = self.segments.get(index) else {
+            return url_segments.is_empty();
+        };
+
+        let ranks_len = specificity.ranks.len();
+        let rank = pattern_segment.segment.rank();
+
+        let taken = match (&pattern_segment.segment, url_segments.first()) {
+            (Segment::CatchAll(name), Some(_)) => {
+                params.insert(name.to_string(), url_segments.join("/"));
+                specificity.ranks.extend(url_segments.iter().map(|_| rank));
+                true
+            }
+            (Segment::Static(literal), Some(url_segment)) if literal == url_segment => {
+                specificity.ranks.push(rank);
+                self.match_from(index + 1, &url_segments[1..], params, specificity)
+            }
+            (Segment::Param { name, constraint }, Some(url_segment))
+                if constraint.as_ref().map_or(true, |c| c.is_match(url_segment)) =>
+            {
+                params.insert(name.to_string(), url_segment.to_string());
+                specificity.ranks.push(rank);
+
+                if self.match_from(index + 1, &url_segments[1..], params, specificity) {
+                    true
+                } else {
+                    params.remove(name);
+                    false
+                }
+            }
+            _ => false,
+        };
+
+        if taken {
+            return true;
+        }
+
+        specificity.ranks.truncate(ranks_len);
+
+        if pattern_segment.optional {
+            specificity.skipped += 1;
+
+            if self.match_from(index + 1, url_segments, params, specificity) {
+                return true;
+            }
+
+            specificity.skipped -= 1;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url {
+        Url::new(path).unwrap()
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_segments() {
+        let pattern = RoutePattern::parse("/user/:id<uint>/:tab?/*rest").unwrap();
+        let segments = pattern.segments();
+
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[0].segment, Segment::Static("user".to_string()));
+        assert!(matches!(
+            &segments[1].segment,
+            Segment::Param { name, constraint: Some(c) } if name == "id" && c.as_str() == "uint"
+        ));
+        assert!(segments[2].optional);
+        assert_eq!(segments[3].segment, Segment::CatchAll("rest".to_string()));
+        assert_eq!(pattern.as_str(), "/user/:id<uint>/:tab?/*rest");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let invalid = [
+            ("/files/*path/edit", "a catch-all must be the last segment"),
+            ("/a/:/b", "parameter names cannot be empty"),
+            ("/a/*", "parameter names cannot be empty"),
+            ("/a/:id/:id", "duplicate parameter name \"id\""),
+            ("/a/:id<\\d+", "unterminated constraint in \":id<\\\\d+\""),
+        ];
+
+        for (pattern, reason) in invalid {
+            let error = RoutePattern::parse(pattern).unwrap_err();
+            assert_eq!(error.reason, reason, "{pattern}");
+        }
+
+        let error = RoutePattern::parse("/a/:id<(>").unwrap_err();
+        assert!(error.reason.starts_with("invalid constraint <(>"));
+    }
+
+    #[test]
+    fn test_matches_catch_all() {
+        let pattern = RoutePattern::parse("/files/*path").unwrap();
+
+        assert_eq!(pattern.matches(&url("/files/a.txt")), Some(params(&[("path", "a.txt")])));
+        assert_eq!(
+            pattern.matches(&url("/files/docs/guide/intro.md")),
+            Some(params(&[("path", "docs/guide/intro.md")]))
+        );
+        assert_eq!(pattern.matches(&url("/files")), None);
+        assert_eq!(pattern.matches(&url("/files/")), None);
+    }
+
+    #[test]
+    fn test_matches_optional_catch_all() {
+        let pattern = RoutePattern::parse("/files/*path?").unwrap();
+
+        assert_eq!(pattern.matches(&url("/files")), Some(HashMap::new()));
+        assert_eq!(pattern.matches(&url("/files/a/b")), Some(params(&[("path", "a/b")])));
+    }
+
+    #[test]
+    fn test_matches_optional_segments() {
+        let pattern = RoutePattern::parse("/docs/:lang?/intro").unwrap();
+
+        assert_eq!(pattern.matches(&url("/docs/en/intro")), Some(params(&[("lang", "en")])));
+        assert_eq!(pattern.matches(&url("/docs/intro")), Some(HashMap::new()));
+        assert_eq!(pattern.matches(&url("/docs/en/other")), None);
+
+        let pattern = RoutePattern::parse("/posts/:id/edit?").unwrap();
+        assert_eq!(pattern.matches(&url("/posts/1/edit")), Some(params(&[("id", "1")])));
+        assert_eq!(pattern.matches(&url("/posts/1")), Some(params(&[("id", "1")])));
+    }
+
+    #[test]
+    fn test_matches_optional_backtracking() {
+        let pattern = RoutePattern::parse("/:a?/:b").unwrap();
+
+        assert_eq!(pattern.matches(&url("/x")), Some(params(&[("b", "x")])));
+        assert_eq!(pattern.matches(&url("/x/y")), Some(params(&[("a", "x"), ("b", "y")])));
+    }
+
+    #[test]
+    fn test_matches_constraints() {
+        let pattern = RoutePattern::parse("/user/:id<\\d+>").unwrap();
+        assert_eq!(pattern.matches(&url("/user/42")), Some(params(&[("id", "42")])));
+        assert_eq!(pattern.matches(&url("/user/42a")), None);
+        assert_eq!(pattern.matches(&url("/user/bob")), None);
+
+        let pattern = RoutePattern::parse("/v/:n<int>/:s<slug>").unwrap();
+        assert!(pattern.matches(&url("/v/-3/hello-world")).is_some());
+        assert!(pattern.matches(&url("/v/3/Hello")).is_none());
+
+        let pattern = RoutePattern::parse("/item/:id<uuid>").unwrap();
+        assert!(pattern
+            .matches(&url("/item/67e55044-10b1-426f-9247-bb680e5fe0c8"))
+            .is_some());
+        assert!(pattern.matches(&url("/item/67e55044")).is_none());
+    }
+
+    #[test]
+    fn test_constraints_apply_to_decoded_segments() {
+        let pattern = RoutePattern::parse("/tag/:name<[a-z ]+>").unwrap();
+        assert_eq!(pattern.matches(&url("/tag/two%20words")), Some(params(&[("name", "two words")])));
+    }
+
+    #[test]
+    fn test_best_match_precedence() {
+        let patterns = [
+            RoutePattern::parse("/user/*rest").unwrap(),
+            RoutePattern::parse("/user/:name").unwrap(),
+            RoutePattern::parse("/user/:id<uint>").unwrap(),
+            RoutePattern::parse("/user/me").unwrap(),
+        ];
+
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/user/me")).unwrap().0, 3);
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/user/7")).unwrap().0, 2);
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/user/bob")).unwrap().0, 1);
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/user/bob/x")).unwrap().0, 0);
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/other")), None);
+    }
+
+    #[test]
+    fn test_best_match_compares_left_to_right() {
+        let patterns = [
+            RoutePattern::parse("/:a/b").unwrap(),
+            RoutePattern::parse("/a/:b").unwrap(),
+        ];
+
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/a/b")).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_best_match_prefers_fewer_skipped_optionals() {
+        let patterns = [
+            RoutePattern::parse("/docs/:lang?/intro").unwrap(),
+            RoutePattern::parse("/docs/intro").unwrap(),
+            RoutePattern::parse("/docs/:page").unwrap(),
+        ];
+
+        assert_eq!(RoutePattern::best_match(&patterns, &url("/docs/intro")).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_best_match_ties_use_first_pattern() {
+        let patterns = [
+            RoutePattern::parse("/a/:x").unwrap(),
+            RoutePattern::parse("/a/:y").unwrap(),
+        ];
+
+        assert_eq!(
+            RoutePattern::best_match(&patterns, &url("/a/1")),
+            Some((0, params(&[("x", "1")])))
+        );
+    }
+}
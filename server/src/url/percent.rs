@@ -0,0 +1,211 @@
+//! # Percent-Encoding Module
+//!
+//! This module implements percent-encoding and decoding of URL components as defined in RFC 3986, Section 2.1.
+//! When decoding, every `%XX` escape is replaced by the byte it encodes, and the resulting bytes must form valid UTF-8.
+//!
+//! Malformed escapes (a `%` not followed by two hexadecimal digits) and escapes decoding to invalid
+//! UTF-8 are reported as a `PercentDecodeError` instead of being passed through unchanged.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::url::percent::{percent_decode, percent_encode, decode_query_component};
+//!
+//! assert_eq!(percent_encode("hello world"), "hello%20world");
+//! assert_eq!(percent_decode("hello%20world").unwrap(), "hello world");
+//! assert_eq!(decode_query_component("a%26b+c").unwrap(), "a&b c");
+//! assert!(percent_decode("100%").is_err());
+//! ```
+
+use std::{error::Error, fmt::Display};
+
+/// Error returned when a URL component contains an invalid percent-encoding.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PercentDecodeError {
+    /// The component that failed to decode.
+    pub input: String,
+
+    /// Why decoding failed.
+    pub reason: &'static str,
+}
+
+impl Display for PercentDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid percent-encoding in {:?}: {}", self.input, self.reason)
+    }
+}
+
+impl Error for PercentDecodeError {}
+
+/// Decodes all `%XX` escapes in `input`.
+///
+/// Characters that are not part of an escape are kept as-is, including `+`.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::url::percent::percent_decode;
+///
+/// assert_eq!(percent_decode("caf%C3%A9").unwrap(), "café");
+/// assert_eq!(percent_decode("a%2Fb").unwrap(), "a/b");
+/// assert_eq!(percent_decode("a+b").unwrap(), "a+b");
+///
+/// let error = percent_decode("%zz").unwrap_err();
+/// assert_eq!(error.reason, "'%' must be followed by two hexadecimal digits");
+/// ```
+pub fn percent_decode(input: &str) -> Result<String, PercentDecodeError> {
+    decode(input, false)
+}
+
+/// Decodes a query string key or value.
+///
+/// Behaves like `percent_decode`, but additionally decodes `+` as a space, following the
+/// `application/x-www-form-urlencoded` convention used by HTML forms.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::url::percent::decode_query_component;
+///
+/// assert_eq!(decode_query_component("rust+lang").unwrap(), "rust lang");
+/// assert_eq!(decode_query_component("1%2B1").unwrap(), "1+1");
+/// ```
+pub fn decode_query_component(input: &str) -> Result<String, PercentDecodeError> {
+    decode(input, true)
+}
+
+/// Percent-encodes every byte of `input` outside the RFC 3986 unreserved set (`A-Z a-z 0-9 - . _ ~`).
+///
+/// The result is safe to use as a path segment, query key or query value.
+///
+/// # Examples
+///
+/// ```rust
+/// use server::url::percent::percent_encode;
+///
+/// assert_eq!(percent_encode("a&b=c d"), "a%26b%3Dc%20d");
+/// assert_eq!(percent_encode("café"), "caf%C3%A9");
+/// assert_eq!(percent_encode("safe-._~"), "safe-._~");
+/// ```
+pub fn percent_encode(input: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut encoded = String::with_capacity(input.len());
+
+    for &b in input.as_bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(b as char);
+        } else {
+            encoded.push('%');
+            encoded.push(HEX[(b >> 4) as usize] as char);
+            encoded.push(HEX[(b & 0x0f) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+fn decode(input: &str, plus_as_space: bool) -> Result<String, PercentDecodeError> {
+    let needs_decoding = input.contains('%') || (plus_as_space && input.contains('+'));
+    if !needs_decoding {
+        return Ok(input.to_string());
+    }
+
+    let error = |reason| PercentDecodeError {
+        input: input.to_string(),
+        reason,
+    };
+
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let high = bytes.get(i + 1).and_then(|b| hex_value(*b));
+                let low = bytes.get(i + 2).and_then(|b| hex_value(*b));
+
+                match (high, low) {
+                    (Some(high), Some(low)) => decoded.push(high << 4 | low),
+                    _ => return Err(error("'%' must be followed by two hexadecimal digits")),
+                }
+
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| error("decoded bytes are not valid UTF-8"))
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode_valid() {
+        assert_eq!(percent_decode("").unwrap(), "");
+        assert_eq!(percent_decode("plain").unwrap(), "plain");
+        assert_eq!(percent_decode("hello%20world").unwrap(), "hello world");
+        assert_eq!(percent_decode("%e2%82%ac%E2%82%AC").unwrap(), "€€");
+        assert_eq!(percent_decode("%25").unwrap(), "%");
+        assert_eq!(percent_decode("a+b").unwrap(), "a+b");
+    }
+
+    #[test]
+    fn test_percent_decode_invalid_escapes() {
+        for input in ["%", "abc%", "%2", "%2g", "%g2", "a%%20"] {
+            let error = percent_decode(input).unwrap_err();
+            assert_eq!(error.input, input);
+            assert_eq!(error.reason, "'%' must be followed by two hexadecimal digits");
+        }
+    }
+
+    #[test]
+    fn test_percent_decode_invalid_utf8() {
+        let error = percent_decode("%C3%28").unwrap_err();
+        assert_eq!(error.reason, "decoded bytes are not valid UTF-8");
+        assert!(percent_decode("%FF").is_err());
+    }
+
+    #[test]
+    fn test_decode_query_component() {
+        assert_eq!(decode_query_component("a+b%2Bc").unwrap(), "a b+c");
+        assert_eq!(decode_query_component("a%26b%3Dc").unwrap(), "a&b=c");
+        assert!(decode_query_component("+%").is_err());
+    }
+
+    #[test]
+    fn test_percent_encode_round_trip() {
+        for input in ["", "plain", "a b/c?d#e", "100%", "€ & ünïcode", "+="] {
+            assert_eq!(percent_decode(&percent_encode(input)).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = percent_decode("%zz").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid percent-encoding in \"%zz\": '%' must be followed by two hexadecimal digits"
+        );
+    }
+}
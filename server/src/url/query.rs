@@ -0,0 +1,259 @@
+//! # Query Module
+//!
+//! This module defines the `QueryParams` struct, which holds the parameters of a URL query string.
+//!
+//! Unlike a map, `QueryParams` keeps every parameter in the order it appeared, including repeated
+//! keys (`?tag=a&tag=b`) and valueless flag-style keys (`?verbose`). It can be serialized back into
+//! a query string using the `Display` trait.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use server::url::query::QueryParams;
+//!
+//! let query = QueryParams::parse("tag=a&verbose&tag=b%20c").unwrap();
+//!
+//! assert_eq!(query.get("tag"), Some("a"));
+//! assert_eq!(query.get_all("tag"), vec!["a", "b c"]);
+//! assert!(query.contains_key("verbose"));
+//! assert_eq!(query.to_string(), "tag=a&verbose&tag=b%20c");
+//! ```
+
+use std::fmt::Display;
+
+use super::percent::{decode_query_component, percent_encode, PercentDecodeError};
+
+/// Ordered, multi-valued query string parameters.
+///
+/// Each parameter is a key with an optional value: `?key=value` and `?key=` have a value
+/// (`"value"` and `""` respectively), while `?key` has none.
+///
+/// ## Examples
+///
+/// ```rust
+/// use server::url::query::QueryParams;
+///
+/// let mut query = QueryParams::new();
+/// query.append("q", Some("rust lang"));
+/// query.append("page", Some("2"));
+/// query.append("debug", None);
+///
+/// assert_eq!(query.to_string(), "q=rust%20lang&page=2&debug");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct QueryParams {
+    params: Vec<(String, Option<String>)>,
+}
+
+impl QueryParams {
+    /// Creates an empty set of query parameters.
+    pub fn new() -> QueryParams {
+        QueryParams { params: Vec::new() }
+    }
+
+    /// Parses a query string (without the leading `?`).
+    ///
+    /// Parameters are separated by `&` and keys are separated from values by the first `=`.
+    /// Keys and values are percent-decoded, with `+` decoded as a space. Empty parameters and
+    /// parameters with an empty key (e.g. `=value`) are skipped.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(QueryParams)`: The parsed parameters, in order.
+    /// - `Err(PercentDecodeError)`: If a key or value contains an invalid percent-encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use server::url::query::QueryParams;
+    ///
+    /// let query = QueryParams::parse("a=1&&=2&b").unwrap();
+    /// assert_eq!(query.iter().collect::<Vec<_>>(), vec![("a", Some("1")), ("b", None)]);
+    ///
+    /// assert!(QueryParams::parse("a=%zz").is_err());
+    /// ```
+    pub fn parse(query: &str) -> Result<QueryParams, PercentDecodeError> {
+        let params = query
+            .split('&')
+            .filter_map(|s| {
+                let (key, value) = match s.split_once('=') {
+                    Some((key, value)) => (key, Some(value)),
+                    None => (s, None),
+                };
+
+                if !key.is_empty() {
+                    Some((key, value))
+                } else {
+                    None
+                }
+            })
+            .map(|(key, value)| {
+                Ok((
+                    decode_query_component(key)?,
+                    value.map(decode_query_component).transpose()?,
+                ))
+            })
+            .collect::<Result<Vec<_>, PercentDecodeError>>()?;
+
+        Ok(QueryParams { params })
+    }
+
+    /// Returns the first value of `key`.
+    ///
+    /// Flag-style keys without a value yield `Some("")`; use `contains_key` to only test for presence.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_deref().unwrap_or(""))
+    }
+
+    /// Returns all values of `key`, in order of appearance.
+    ///
+    /// Flag-style occurrences without a value are returned as `""`.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_deref().unwrap_or(""))
+            .collect()
+    }
+
+    /// Returns `true` if `key` appears at least once, with or without a value.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.params.iter().any(|(k, _)| k == key)
+    }
+
+    /// Appends a parameter, keeping any existing values of `key`.
+    pub fn append(&mut self, key: &str, value: Option<&str>) {
+        self.params
+            .push((key.to_string(), value.map(|v| v.to_string())));
+    }
+
+    /// Removes all values of `key`.
+    pub fn remove(&mut self, key: &str) {
+        self.params.retain(|(k, _)| k != key);
+    }
+
+    /// Iterates over all parameters as `(key, value)` pairs, in order of appearance.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_deref()))
+    }
+
+    /// Returns the number of parameters, counting repeated keys separately.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for QueryParams {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let params = iter
+            .into_iter()
+            .map(|(k, v)| (k.into(), Some(v.into())))
+            .collect();
+
+        QueryParams { params }
+    }
+}
+
+impl Display for QueryParams {
+    /// Serializes the parameters into a query string (without the leading `?`).
+    ///
+    /// Keys and values are percent-encoded, so the output parses back into equal `QueryParams`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, "&")?;
+            }
+
+            write!(f, "{}", percent_encode(key))?;
+
+            if let Some(value) = value {
+                write!(f, "={}", percent_encode(value))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_preserves_order_and_duplicates() {
+        let query = QueryParams::parse("b=2&a=1&b=3").unwrap();
+
+        assert_eq!(
+            query.iter().collect::<Vec<_>>(),
+            vec![("b", Some("2")), ("a", Some("1")), ("b", Some("3"))]
+        );
+        assert_eq!(query.get("b"), Some("2"));
+        assert_eq!(query.get_all("b"), vec!["2", "3"]);
+        assert_eq!(query.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_valueless_and_empty_values() {
+        let query = QueryParams::parse("verbose&empty=&x=1").unwrap();
+
+        assert_eq!(
+            query.iter().collect::<Vec<_>>(),
+            vec![("verbose", None), ("empty", Some("")), ("x", Some("1"))]
+        );
+        assert_eq!(query.get("verbose"), Some(""));
+        assert!(query.contains_key("verbose"));
+        assert!(!query.contains_key("missing"));
+        assert_eq!(query.get("missing"), None);
+        assert!(query.get_all("missing").is_empty());
+    }
+
+    #[test]
+    fn test_parse_skips_empty_keys() {
+        let query = QueryParams::parse("&=value&&key=v=w").unwrap();
+        assert_eq!(query.iter().collect::<Vec<_>>(), vec![("key", Some("v=w"))]);
+        assert!(QueryParams::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_decodes_components() {
+        let query = QueryParams::parse("q=a%26b&k%3D=x+y").unwrap();
+        assert_eq!(query.get("q"), Some("a&b"));
+        assert_eq!(query.get("k="), Some("x y"));
+        assert!(QueryParams::parse("q=%").is_err());
+    }
+
+    #[test]
+    fn test_append_and_remove() {
+        let mut query = QueryParams::new();
+        query.append("tag", Some("a"));
+        query.append("flag", None);
+        query.append("tag", Some("b"));
+        assert_eq!(query.to_string(), "tag=a&flag&tag=b");
+
+        query.remove("tag");
+        assert_eq!(query.to_string(), "flag");
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let input = "q=rust%20%26%20go&tag=a&tag=b&verbose&empty=";
+        let query = QueryParams::parse(input).unwrap();
+
+        assert_eq!(query.to_string(), input);
+        assert_eq!(QueryParams::parse(&query.to_string()).unwrap(), query);
+    }
+
+    #[test]
+    fn test_from_iterator() {
+        let query = QueryParams::from_iter([("a", "1"), ("b", "2")]);
+        assert_eq!(query.to_string(), "a=1&b=2");
+    }
+}